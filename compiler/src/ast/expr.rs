@@ -4,7 +4,7 @@ use std::iter::FromIterator;
 use std::path::{Path, PathBuf};
 use std::convert::AsRef;
 use std::collections::{HashMap, LinkedList, HashSet};
-use num::{FromPrimitive, ToPrimitive, BigRational, BigInt, Signed};
+use num::{FromPrimitive, ToPrimitive, BigRational, BigInt, Signed, Zero, One};
 
 use crate::*;
 
@@ -74,6 +74,27 @@ fn for_each(
         .collect()
 }
 
+/// The positive rational `y` such that `y^n = x`, if there is one.
+fn exact_root(x: &BigRational, n: i32) -> Option<BigRational> {
+    if n < 0 {
+        return exact_root(&x.recip(), -n)
+    }
+    if n == 0 || !x.is_positive() {
+        return None
+    }
+    let root = |x: &BigInt| -> Option<BigInt> {
+        let guess = x.to_f64()?.powf(1.0 / n as f64).round();
+        let guess = BigInt::from_f64(guess)?;
+        let candidates = vec![&guess - 1, guess.clone(), &guess + 1];
+        candidates
+            .into_iter()
+            .find(|y| num::pow(y.clone(), n as usize) == *x)
+    };
+    let numer = root(x.numer())?;
+    let denom = root(x.denom())?;
+    Some(BigRational::new(numer, denom))
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // EXPRESSION AST
 ///////////////////////////////////////////////////////////////////////////////
@@ -116,6 +137,15 @@ pub enum Expr {
     Fraction(Box<Expr>),
     Product(Vec<Expr>),
     Call(Box<FunCall>),
+    /// x^n, for a rational exponent n.
+    Pow(Box<Expr>, BigRational),
+    /// The common (base ten) logarithm, as in `pH = -log[H⁺]`.
+    Log(Box<Expr>),
+    /// A string literal, e.g. a reaction given to a function.
+    Str(String),
+    /// `{key: value, ...}`, e.g. initial concentrations or the named results
+    /// of a calculation, in the order given.
+    Record(Vec<(String, Expr)>),
 }
 
 impl Expr {
@@ -234,6 +264,69 @@ impl Expr {
             _ => false
         }
     }
+    pub fn is_pow(&self) -> bool {
+        matches!(self, Expr::Pow(_, _))
+    }
+    pub fn pow(self, n: BigRational) -> Self {
+        Expr::Pow(Box::new(self), n)
+    }
+    /// The base and exponent, where anything that isn't a power is its own
+    /// base to the first power.
+    fn into_power(self) -> (Expr, BigRational) {
+        match self {
+            Expr::Pow(x, n) => (*x, n),
+            Expr::Fraction(x) => (*x, -BigRational::one()),
+            x => (x, BigRational::one()),
+        }
+    }
+    /// Replaces every occurrence of the named symbol.
+    pub fn substitute(self, name: &str, value: &Expr) -> Expr {
+        let name = name.to_owned();
+        let value = value.clone();
+        self.trans(Rc::new(move |x| match x {
+            Expr::Sym(x) if x.id() == name => value.clone(),
+            x => x,
+        }))
+    }
+    /// Solves `self = value` for the named symbol, provided `self` evaluates
+    /// to a number times a power of that symbol (e.g. an equilibrium constant
+    /// once every other concentration has been substituted).
+    ///
+    /// Roots are exact where possible, otherwise a floating point
+    /// approximation.
+    pub fn solve_for(self, name: &str, value: &BigRational) -> Option<BigRational> {
+        let mut coefficient = BigRational::one();
+        let mut exponent = BigRational::zero();
+        for factor in self.eval().products() {
+            match factor.into_power() {
+                (Expr::Num(x), n) if n.is_one() => {
+                    coefficient *= x;
+                }
+                (Expr::Num(x), n) if n == -BigRational::one() => {
+                    coefficient /= x;
+                }
+                (Expr::Sym(x), n) if x.id() == name => {
+                    exponent += n;
+                }
+                _ => return None,
+            }
+        }
+        if exponent.is_zero() || coefficient.is_zero() {
+            return None
+        }
+        let power = value / coefficient;
+        if exponent.is_one() {
+            return Some(power)
+        }
+        if exponent.is_integer() {
+            let n = exponent.to_integer().to_i32()?;
+            if let Some(x) = exact_root(&power, n) {
+                return Some(x)
+            }
+        }
+        let x = power.to_f64()?.powf(exponent.recip().to_f64()?);
+        BigRational::from_f64(x).filter(|_| x.is_finite())
+    }
     pub fn unit_fraction(val: Expr) -> Expr {
         // Expr::Fraction(Box::new(val))
         val.reciprocal()
@@ -257,6 +350,10 @@ impl Expr {
             Expr::Fraction(x) => x.is_multiplicative_identity(),
             Expr::Product(xs) => xs.iter().all(|x| x.is_multiplicative_identity()),
             Expr::Call(_) => false,
+            Expr::Pow(x, n) => n.is_zero() || x.is_multiplicative_identity(),
+            Expr::Log(_) => false,
+            Expr::Str(_) => false,
+            Expr::Record(_) => false,
         }
     }
     pub fn multiplicative_identity() -> Self {
@@ -291,6 +388,14 @@ impl Expr {
                     key_args,
                 }))
             }
+            Expr::Pow(x, n) => Expr::Pow(Box::new(x.trans(f.clone())), n),
+            Expr::Log(x) => Expr::Log(Box::new(x.trans(f.clone()))),
+            Expr::Str(x) => Expr::Str(x),
+            Expr::Record(xs) => Expr::Record(
+                xs  .into_iter()
+                    .map(|(key, x)| (key, x.trans(f.clone())))
+                    .collect::<Vec<_>>()
+            ),
         };
         f(result)
     }
@@ -321,6 +426,10 @@ impl Expr {
             Expr::Call(x) => Expr::Fraction(Box::new(
                 Expr::Call(x.clone())
             )),
+            Expr::Pow(x, n) => Expr::Pow(x.clone(), -n.clone()),
+            Expr::Log(_) | Expr::Str(_) | Expr::Record(_) => Expr::Fraction(Box::new(
+                self.clone()
+            )),
         }
     }
     fn is_equal(&self, other: &Expr) -> bool {
@@ -384,6 +493,15 @@ impl Expr {
             (Expr::Sym(x), Expr::Sym(y)) => {x == y}
             (Expr::Fraction(x), Expr::Fraction(y)) => {x.is_equal(y)}
             (Expr::Product(xs), Expr::Product(ys)) => match_xs_ys(xs, ys),
+            (Expr::Pow(x, n), Expr::Pow(y, m)) => n == m && x.is_equal(y),
+            (Expr::Log(x), Expr::Log(y)) => x.is_equal(y),
+            (Expr::Str(x), Expr::Str(y)) => x == y,
+            (Expr::Record(xs), Expr::Record(ys)) => {
+                xs.len() == ys.len() && xs
+                    .iter()
+                    .zip(ys.iter())
+                    .all(|((k1, x), (k2, y))| k1 == k2 && x.is_equal(y))
+            }
             (Expr::Call(x), Expr::Call(y)) if x.name == y.name => {
                 let check2 = match_xs_ys(
                     &x.pos_args,
@@ -401,6 +519,10 @@ impl Expr {
             (Expr::Fraction(_), _) => false,
             (Expr::Product(_), _) => false,
             (Expr::Call(_), _) => false,
+            (Expr::Pow(_, _), _) => false,
+            (Expr::Log(_), _) => false,
+            (Expr::Str(_), _) => false,
+            (Expr::Record(_), _) => false,
         }
     }
     fn hoist_products(self, sink: &mut Vec<Expr>) {
@@ -428,6 +550,18 @@ impl Expr {
             Expr::Call(x) => {
                 sink.push(Expr::Call(x));
             }
+            Expr::Pow(x, n) => {
+                sink.push(Expr::Pow(x, n));
+            }
+            Expr::Log(x) => {
+                sink.push(Expr::Log(x));
+            }
+            Expr::Str(x) => {
+                sink.push(Expr::Str(x));
+            }
+            Expr::Record(xs) => {
+                sink.push(Expr::Record(xs));
+            }
         }
     }
     fn products(self) -> Vec<Self> {
@@ -474,6 +608,17 @@ impl Expr {
                         Expr::multiplicative_identity(),
                     )
                 }
+                // COMBINE POWERS OF THE SAME BASE
+                if left.is_pow() || right.is_pow() {
+                    let (left_base, left_n) = left.clone().into_power();
+                    let (right_base, right_n) = right.clone().into_power();
+                    if left_base.is_equal(&right_base) {
+                        return (
+                            Expr::multiplicative_identity(),
+                            Expr::Pow(Box::new(left_base), left_n + right_n),
+                        )
+                    }
+                }
                 // REDUCE INT FRACTIONS
                 use num::Integer;
                 fn only_int(x: BigRational) -> Option<BigRational> {
@@ -577,12 +722,68 @@ impl Expr {
                     .collect::<Vec<_>>();
                 Expr::Product(xs).cancel_matching_factors()
             }
+            Expr::Pow(base, n) => {
+                let base = base
+                    .simplify_impl()
+                    .unwrap_or(Expr::multiplicative_identity());
+                if n.is_zero() {
+                    return None
+                }
+                if n.is_one() {
+                    return Some(base)
+                }
+                match base {
+                    Expr::Num(x) if n.is_integer() => {
+                        let n = n.to_integer().to_i32()?;
+                        Some(Expr::Num(x.pow(n)))
+                    }
+                    Expr::Num(x) if x.is_positive() => {
+                        let x = x.to_f64()?.powf(n.to_f64()?);
+                        Some(Expr::Num(BigRational::from_f64(x)?))
+                    }
+                    Expr::Pow(x, m) => Expr::Pow(x, m * n).simplify_impl(),
+                    Expr::Fraction(x) => Expr::Pow(x, -n).simplify_impl(),
+                    Expr::Product(xs) => {
+                        let xs = xs
+                            .into_iter()
+                            .map(|x| Expr::Pow(Box::new(x), n.clone()))
+                            .collect();
+                        Expr::Product(xs).simplify_impl()
+                    }
+                    base => Some(Expr::Pow(Box::new(base), n)),
+                }
+            }
+            Expr::Log(x) => {
+                let x = x
+                    .simplify_impl()
+                    .unwrap_or(Expr::multiplicative_identity());
+                match x {
+                    Expr::Num(x) if x.is_positive() => {
+                        let x = crate::numbers::Number::from(x).log10()?;
+                        Some(Expr::Num(x.into()))
+                    }
+                    x => Some(Expr::Log(Box::new(x))),
+                }
+            }
+            Expr::Str(x) => Some(Expr::Str(x)),
+            Expr::Record(xs) => {
+                let xs = xs
+                    .into_iter()
+                    .map(|(key, x)| {
+                        let x = x
+                            .simplify_impl()
+                            .unwrap_or(Expr::multiplicative_identity());
+                        (key, x)
+                    })
+                    .collect::<Vec<_>>();
+                Some(Expr::Record(xs))
+            }
             Expr::Call(call) => {
                 let pos_args = call.pos_args
                     .into_iter()
                     .map(|x| {
                         x   .simplify_impl()
-                            .unwrap_or_else(|| unimplemented!())
+                            .unwrap_or(Expr::multiplicative_identity())
                     })
                     .collect::<Vec<_>>();
                 let key_args = call.key_args
@@ -716,6 +917,48 @@ impl Expr {
                     args,
                 )
             }
+            Expr::Pow(base, n) => {
+                let base = match **base {
+                    Expr::Product(_) | Expr::Fraction(_) | Expr::Pow(_, _) => {
+                        format!("({})", base)
+                    }
+                    _ => base.to_string(),
+                };
+                if n.is_integer() {
+                    let n = format!("{}", n)
+                        .chars()
+                        .map(|x: char| {
+                            match x {
+                                '-' => '⁻',
+                                '0' => '⁰',
+                                '1' => '¹',
+                                '2' => '²',
+                                '3' => '³',
+                                '4' => '⁴',
+                                '5' => '⁵',
+                                '6' => '⁶',
+                                '7' => '⁷',
+                                '8' => '⁸',
+                                '9' => '⁹',
+                                x => x,
+                            }
+                        })
+                        .collect::<String>();
+                    format!("{}{}", base, n)
+                } else {
+                    format!("{}^({})", base, n)
+                }
+            }
+            Expr::Log(x) => format!("log({})", x),
+            Expr::Str(x) => format!("{:?}", x),
+            Expr::Record(xs) => {
+                let xs = xs
+                    .iter()
+                    .map(|(key, x)| format!("{}: {}", key, x))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{{{}}}", xs)
+            }
         }
     }
     pub fn eval(self) -> Self {
@@ -778,6 +1021,18 @@ impl std::fmt::Debug for Expr {
             Expr::Product(xs) => {
                 write!(f, "Expr::Product({:?})", xs)
             }
+            Expr::Pow(x, n) => {
+                write!(f, "Expr::Pow({:?}, {})", x, n)
+            }
+            Expr::Log(x) => {
+                write!(f, "Expr::Log({:?})", x)
+            }
+            Expr::Str(x) => {
+                write!(f, "Expr::Str({:?})", x)
+            }
+            Expr::Record(xs) => {
+                write!(f, "Expr::Record({:?})", xs)
+            }
             Expr::Call(fun_call) => {
                 let mut args = Vec::<String>::new();
                 for arg in fun_call.pos_args.iter() {
@@ -859,5 +1114,154 @@ mod tests {
             Expr::unit_fraction(Expr::con("f"))
         );
     }
-}
 
+    #[test]
+    fn molar_mass() {
+        let run = |source: &str| {
+            Expr::from_str(source).unwrap().eval().to_string()
+        };
+        assert_eq!(run("molar_mass(H2O)"), "18.015 * g * mol⁻¹");
+        assert_eq!(run("molar_mass(NaCl)"), "58.44 * g * mol⁻¹");
+        assert_eq!(run("percent(H2O, element = O)"), "88.809326");
+        assert_eq!(run("molar_mass(CuSO4·5H2O)"), "249.677 * g * mol⁻¹");
+        assert_eq!(run("empirical(masses = {C: 40.0, H: 6.7, O: 53.3})"), "CH2O");
+        assert_eq!(run("molecular(masses = {C: 40.0, H: 6.7, O: 53.3}, molar_mass = 180.16)"), "C6H12O6");
+        // Reciprocal units aren't formulas with a charge.
+        assert_eq!(run("J * mol^-1 * K^-1"), "J * mol⁻¹ * K⁻¹");
+        assert_eq!(run("C^-1 * N^-1"), "C⁻¹ * N⁻¹");
+    }
+
+    #[test]
+    fn equilibrium() {
+        let source = r#"equilibrium(
+            reaction = "H2(g) + I2(g) <=> 2HI(g)",
+            K = 4,
+            initial = {H2: 1, I2: 1}
+        )"#;
+        let result = Expr::from_str(source).unwrap().eval();
+        assert_eq!(
+            result.to_string(),
+            r#"{[H2]: 1/2, [I2]: 1/2, [HI]: 1, x: 1/2, approximation: "not valid"}"#
+        );
+    }
+
+    #[test]
+    fn thermodynamics() {
+        let run = |source: &str| {
+            Expr::from_str(source).unwrap().eval().to_string()
+        };
+        let reaction = r#""CH4(g) + 2O2(g) -> CO2(g) + 2H2O(l)""#;
+        assert_eq!(run(&format!("enthalpy(reaction = {})", reaction)), "-890.36 * kJ * mol⁻¹");
+        assert_eq!(run(&format!("entropy(reaction = {})", reaction)), "-243 * J * mol⁻¹ * K⁻¹");
+        assert_eq!(run(&format!("gibbs(reaction = {}, T = 298.15)", reaction)), "-817.90955 * kJ * mol⁻¹");
+    }
+
+    #[test]
+    fn hess() {
+        let source = r#"hess(
+            reaction = "C(s) + 2H2(g) -> CH4(g)",
+            steps = {
+                "C(s) + O2(g) -> CO2(g)": -393.5,
+                "H2(g) + 0.5O2(g) -> H2O(l)": -285.8,
+                "CH4(g) + 2O2(g) -> CO2(g) + 2H2O(l)": -890.3
+            }
+        )"#;
+        assert_eq!(Expr::from_str(source).unwrap().eval().to_string(), "-74.8 * kJ * mol⁻¹");
+    }
+
+    #[test]
+    fn limiting_reagent() {
+        let run = |source: &str| {
+            Expr::from_str(source).unwrap().eval().to_string()
+        };
+        assert_eq!(
+            run(r#"limiting(reaction = "N2 + H2 -> NH3", amounts = {N2: 2 * mol, H2: 6.048 * g})"#),
+            concat!(
+                "{limiting: H2, theoretical: {NH3: 34.062 * g}, leftover: {N2: 28.014 * g}, ",
+                "steps: {moles of N2: 2 * mol, moles of H2: 3 * mol, ",
+                "moles of reaction N2 allows (÷ 1): 2 * mol, moles of reaction H2 allows (÷ 3): mol, ",
+                "H2 is the limiting reagent: mol, theoretical yield of NH3: 34.062 * g, ",
+                "N2 left over: 28.014 * g}}",
+            )
+        );
+        assert!(
+            run(r#"limiting(reaction = "AgNO3 + NaCl -> AgCl + NaNO3", amounts = {AgNO3: 100 * mL * 0.5 * M, NaCl: 1168.8 * mg})"#)
+                .starts_with("{limiting: NaCl, theoretical: {AgCl: 2.8664 * g, NaNO3: 1.69988 * g}, leftover: {AgNO3: 5.09622 * g}, steps: {")
+        );
+        assert_eq!(
+            run(r#"percent_yield(reaction = "N2 + 3H2 -> 2NH3", amounts = {N2: 2 * mol, H2: 3 * mol}, actual = {NH3: 25.5465 * g})"#),
+            "75"
+        );
+    }
+
+    #[test]
+    fn solutions() {
+        let run = |source: &str| {
+            Expr::from_str(source).unwrap().eval().to_string()
+        };
+        assert_eq!(run("molarity(moles = mol(0.5), volume = mL(250))"), "2 * M");
+        assert_eq!(run("molarity(substance = NaCl, mass = g(11.688), volume = mL(100))"), "2 * M");
+        assert_eq!(run("molality(moles = mmol(1500), solvent = kg(0.5))"), "3 * mol * kg⁻¹");
+        assert_eq!(run("molality(substance = C6H12O6, mass = g(90.078), solvent = g(250))"), "2 * mol * kg⁻¹");
+        assert_eq!(run("mass_percent(solute = g(5), solution = g(125))"), "4");
+        assert_eq!(run("ppm(solute = mg(3), solution = kg(1))"), "3");
+        assert_eq!(run("dilution(C1 = M(12), V1 = mL(50), C2 = M(3))"), "200 * mL");
+        assert_eq!(run("dilution(C2 = mM(500), V2 = L(3), C1 = M(6))"), "1/4 * L");
+        assert_eq!(run("dilution(C1 = M(6), V1 = mL(25), V2 = mL(75))"), "2 * M");
+        assert_eq!(run("mix(C1 = M(1), V1 = mL(100), C2 = M(3), V2 = mL(300))"), "5/2 * M");
+    }
+
+    #[test]
+    fn acids_and_bases() {
+        let run = |source: &str| {
+            Expr::from_str(source).unwrap().eval().to_string()
+        };
+        assert_eq!(run("log(1000)"), "3");
+        assert_eq!(run("log(x)"), "log(x)");
+        assert_eq!(run("pH(H = M(0.001))"), "3");
+        assert_eq!(run("pH(OH = 0.01)"), "12");
+        assert_eq!(run("pOH(pH = 4.5)"), "19/2");
+        assert_eq!(run("hydronium(pH = 3)"), "0.001 * M");
+        assert_eq!(run("hydroxide(pH = 11)"), "0.001 * M");
+        // Out of range is left alone rather than crashing.
+        let overflow = Expr::from_str("hydronium(pH = -400.5)").unwrap().eval();
+        assert!(matches!(overflow, Expr::Call(_)));
+        assert_eq!(run("weak_acid(C = M(0.1), Ka = 1.8e-5)"), "2.875277");
+        assert_eq!(run("buffer(pKa = 4.74, acid = M(0.1), base = M(0.1))"), "4.74");
+        let source = "titration(analyte = \"strong acid\", concentration = M(0.1), volume = mL(25), titrant = M(0.1), added = mL(25))";
+        assert_eq!(run(source), "{pH: 7, equivalence: 25 * mL}");
+        let source = "titration(analyte = \"weak acid\", K = 1.8e-5, concentration = M(0.1), volume = mL(25), titrant = M(0.1), added = mL(25))";
+        assert_eq!(run(source), "{pH: 8.721826, equivalence: 25 * mL}");
+        let source = "titration(analyte = \"strong base\", concentration = M(0.1), volume = mL(20), titrant = M(0.2), step = mL(5))";
+        assert_eq!(
+            run(source),
+            "{equivalence: 10 * mL, curve: {0 * mL: 13, 5 * mL: 12.60206, 10 * mL: 7, 15 * mL: 1.5440681, 20 * mL: 1.30103}}"
+        );
+        let source = "titration(analyte = \"strong acid\", concentration = M(0.1), volume = mL(25), titrant = M(0.1), step = mL(0.0000001))";
+        assert!(matches!(Expr::from_str(source).unwrap().eval(), Expr::Call(_)));
+    }
+
+    #[test]
+    fn gas_laws() {
+        let run = |source: &str| {
+            Expr::from_str(source).unwrap().eval().to_string()
+        };
+        assert_eq!(run("ideal_gas(V = L(22.414), n = mol(1), T = K(273.15))"), "0.9999986 * atm");
+        assert_eq!(run("ideal_gas(P = kPa(101.325), V = L(22.414), T = K(273.15))"), "1.0000013 * mol");
+        assert_eq!(run("ideal_gas(P = atm(2), n = mol(0.5), T = 300)"), "6.1543026 * L");
+        assert_eq!(run("ideal_gas(P = mmHg(760), V = L(5), T = K(298))"), "0.2044731 * mol");
+        assert_eq!(run("ideal_gas(P = atm(1), V = mL(500), mass = g(0.88), T = K(300))"), "43.32629 * g * mol⁻¹");
+        assert_eq!(run("combined_gas(P1 = kPa(100), V1 = L(2), T1 = K(300), V2 = L(1), T2 = K(450))"), "300 * kPa");
+        assert_eq!(run("combined_gas(P1 = torr(760), V1 = mL(300), T1 = K(300), P2 = atm(2), T2 = K(600))"), "300 * mL");
+        assert_eq!(run("gas_density(P = atm(1), molar_mass = 32, T = K(273.15))"), "1.4276811 * g * L⁻¹");
+        assert_eq!(run("gas_density(density = 1.977 * g * L^-1, P = atm(1), T = K(273.15))"), "44.312416 * g * mol⁻¹");
+        assert_eq!(run("partial_pressure(fraction = 0.21, total = kPa(100))"), "21 * kPa");
+        assert_eq!(run("partial_pressure(moles = {N2: mol(4), O2: mol(1)}, total = atm(2))"), "{N2: 8/5 * atm, O2: 2/5 * atm}");
+        assert_eq!(run("total_pressure(partials = {N2: torr(600), O2: torr(160)})"), "760 * torr");
+        // Every variable given, or one the relation doesn't have.
+        let overdetermined = "ideal_gas(P = atm(1), V = L(1), n = mol(1), T = K(1))";
+        assert!(matches!(Expr::from_str(overdetermined).unwrap().eval(), Expr::Call(_)));
+        let unknown = "combined_gas(P1 = atm(1), V1 = L(1), T1 = K(300), V2 = L(2), n = mol(1))";
+        assert!(matches!(Expr::from_str(unknown).unwrap().eval(), Expr::Call(_)));
+    }
+}
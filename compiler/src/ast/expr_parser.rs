@@ -17,7 +17,7 @@ use nom::{
     branch::alt,
     character::complete::{alpha1},
     character::complete::alphanumeric1,
-    combinator::{cut, map, opt, verify, not},
+    combinator::{cut, map, map_opt, opt, verify, not},
     error::{context, VerboseError},
     multi::{many0, many1},
     sequence::{preceded, terminated},
@@ -48,8 +48,18 @@ pub fn is_uppercase(chr: char) -> bool {
 }
 
 pub fn parse_number(source: &str) -> Result<(&str, BigRational), Error<&str>> {
-    let (source, val) = nom::number::complete::double(source)?;
-    let mut val = BigRational::from_f64(val).unwrap();
+    // Exact, so `6.048` isn't the nearest `f64`; exponents too large for
+    // that go through `f64` instead, and fail if it overflows.
+    fn to_rational(text: &str) -> Option<BigRational> {
+        match crate::numbers::Number::decimal(text) {
+            Some(x) => Some(x.into()),
+            None => BigRational::from_f64(text.parse::<f64>().ok()?),
+        }
+    }
+    let (source, mut val) = map_opt(
+        recognize(nom::number::complete::double),
+        to_rational,
+    )(source)?;
     fn exponent_parser(source: &str) -> Result<(&str, isize), Error<&str>> {
         let (source, _) = char('^')(source)?;
         let (source, number) = digit1(source)?;
@@ -71,7 +81,11 @@ fn parse_ast(source: &str) -> Result<(&str, Expr), Error<&str>> {
     use nom::{eof, named};
     fn inner(source: &str) -> Result<(&str, Expr), Error<&str>> {
         let parsers = (
+            parse_string,
+            parse_record,
             parse_product,
+            parse_formula,
+            parse_log,
             parse_function_call,
             parse_constant,
             parse_literal,
@@ -104,6 +118,35 @@ fn parse_literal(source: &str) -> Result<(&str, Expr), Error<&str>> {
     Ok((source, ast))
 }
 
+fn parse_string(source: &str) -> Result<(&str, Expr), Error<&str>> {
+    let (source, string) = parser_utils::string::parse_string(source)?;
+    Ok((source, Expr::Str(string)))
+}
+
+/// `{key: value, ...}`, where a key is either a string or a bare word such as
+/// a formula (e.g. `{CH3COOH: 0.1, "H+": 0}`).
+fn parse_record(source: &str) -> Result<(&str, Expr), Error<&str>> {
+    fn key(source: &str) -> Result<(&str, String), Error<&str>> {
+        let bare = take_while1(|c: char| {
+            !matches!(c, ':' | ',' | '{' | '}' | '"') && !c.is_whitespace()
+        });
+        alt((
+            parser_utils::string::parse_string,
+            map(bare, String::from),
+        ))(source)
+    }
+    fn entry(source: &str) -> Result<(&str, (String, Expr)), Error<&str>> {
+        let (source, key) = ws(key)(source)?;
+        let (source, _) = ws(char(':'))(source)?;
+        let (source, value) = parse_ast(source)?;
+        Ok((source, (key, value)))
+    }
+    let (source, _) = ws(char('{'))(source)?;
+    let (source, entries) = separated_list0(parser_utils::comma, entry)(source)?;
+    let (source, _) = ws(char('}'))(source)?;
+    Ok((source, Expr::Record(entries)))
+}
+
 fn parse_constant(source: &str) -> Result<(&str, Expr), Error<&str>> {
     fn parse_reciprocal(source: &str) -> Result<(&str, bool), Error<&str>> {
         let p = opt(tag("^-1"));
@@ -122,9 +165,44 @@ fn parse_constant(source: &str) -> Result<(&str, Expr), Error<&str>> {
     Ok((source, ast))
 }
 
+/// Chemical formulas such as `H2O` or `Ca(OH)2`, which are kept as a constant
+/// with the formula as its name.
+///
+/// Formulas made up of letters alone (e.g. `NaCl`) are already valid constants,
+/// so this only matches formulas with a subscript or parentheses, and only
+/// when every element is in the periodic table (so `GHz(275)` is still a
+/// function call). A formula can't contain whitespace here, so `2 C` is still
+/// two of a unit rather than a coefficient and an element, nor a charge, so
+/// `K^-1` is still the reciprocal of a unit.
+fn parse_formula(source: &str) -> Result<(&str, Expr), Error<&str>> {
+    let group = crate::chem::parser::parse_group_strict;
+    let formula = recognize(verify(group, |node: &crate::chem::data::Node| {
+        node.charge() == 0
+    }));
+    let mut parser = verify(formula, |text: &str| {
+        text.chars().any(|c| c.is_ascii_digit() || c == '(') &&
+        !text.contains(char::is_whitespace)
+    });
+    // An invalid charge is a hard failure for formulas, but here it just
+    // means this isn't one.
+    let (source, text) = parser(source).map_err(|error| match error {
+        nom::Err::Failure(x) => nom::Err::Error(x),
+        x => x,
+    })?;
+    Ok((source, Expr::con(text)))
+}
+
+fn parse_log(source: &str) -> Result<(&str, Expr), Error<&str>> {
+    let (source, _) = tag("log")(source)?;
+    let (source, x) = parens(parse_ast)(source)?;
+    Ok((source, Expr::Log(Box::new(x))))
+}
+
 fn parse_product(source: &str) -> Result<(&str, Expr), Error<&str>> {
     fn inner(source: &str) -> Result<(&str, Expr), Error<&str>> {
         let parsers = (
+            parse_formula,
+            parse_log,
             parse_function_call,
             parse_constant,
             parse_literal,
@@ -147,7 +225,7 @@ fn parse_function_call(source: &str) -> Result<(&str, Expr), Error<&str>> {
     fn keyword_argument(
         source: &str
     ) -> Result<(&str, (String, Expr)), Error<&str>> {
-        let (source, ident) = identifier(source)?;
+        let (source, ident) = ws(identifier)(source)?;
         let (source, _) = ws(char('='))(source)?;
         let (source, ast) = parse_ast(source)?;
         Ok((source, (ident, ast)))
@@ -159,6 +237,12 @@ fn parse_function_call(source: &str) -> Result<(&str, Expr), Error<&str>> {
             parser_utils::comma,
             positional_argument,
         )(source)?;
+        // The comma between the last positional and first keyword argument.
+        let (source, _) = if pos_args.is_empty() {
+            (source, None)
+        } else {
+            opt(parser_utils::comma)(source)?
+        };
         let (source, key_args) = separated_list0(
             parser_utils::comma,
             keyword_argument,
@@ -227,5 +311,20 @@ mod tests {
         run("mole(energy(photon(wavelength = nm(325))))");
         run("J * 3.6808174042676e5");
         run("a(J * 1.0)");
+        run("molar_mass(Ca(OH)2)");
+        run("percent(C6H12O6, element = C)");
+        run("equilibrium(reaction = \"A <=> B\", K = 2, initial = {A: 1, \"B\": 0})");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn number_literals() {
+        let value = |source: &str| parse_number(source).ok().map(|(_, x)| x.to_f64().unwrap());
+        assert_eq!(value("6.048"), Some(6.048));
+        assert_eq!(value("2^3"), Some(8.0));
+        // Exponents too large to be exact go through `f64`.
+        assert_eq!(value("1.5e-2147483648"), Some(0.0));
+        assert_eq!(value("2.5e-20000"), Some(0.0));
+        assert_eq!(value("1e99999999"), None);
+        assert!(value("1e400").is_some());
+    }
+}
@@ -7,7 +7,14 @@ use std::convert::AsRef;
 use std::collections::{HashMap, LinkedList, HashSet, VecDeque};
 use num::{FromPrimitive, ToPrimitive, BigRational, BigInt};
 
-use crate::ast::expr::{Expr, FunCall};
+use crate::ast::expr::{Expr, FunCall, Symbol};
+use crate::chem::acidbase::{self, Analyte, Titration};
+use crate::chem::data::{Element, Node, Reaction};
+use crate::chem::equilibrium::concentration_symbol;
+use crate::chem::empirical::{empirical_formula, molecular_formula};
+use crate::chem::stoichiometry::Quantity;
+use crate::chem::thermo::kilojoules_per_mole;
+use crate::numbers::Number;
 use crate::*;
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -39,6 +46,266 @@ impl ConvertTo<Expr> for Expr {
         Some(self.clone())
     }
 }
+/// Chemical formulas are constants named after the formula (see
+/// `expr_parser::parse_formula`).
+impl ConvertTo<Node> for Expr {
+    fn convert_to(&self) -> Option<Node> {
+        match self {
+            Expr::Sym(Symbol::Con(x)) => {
+                match crate::chem::parser::parse_group_strict(x) {
+                    Ok(("", node)) => Some(node),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+/// Reactions are given as strings, e.g. `"N2 + 3H2 <=> 2NH3"`.
+impl ConvertTo<Reaction> for Expr {
+    fn convert_to(&self) -> Option<Reaction> {
+        match self {
+            Expr::Str(x) => {
+                match crate::chem::parser::parse_reaction_strict(x) {
+                    Ok(("", reaction)) => Some(reaction),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+impl ConvertTo<Vec<(String, Expr)>> for Expr {
+    fn convert_to(&self) -> Option<Vec<(String, Expr)>> {
+        match self {
+            Expr::Record(xs) => Some(xs.clone()),
+            _ => None,
+        }
+    }
+}
+impl ConvertTo<Element> for Expr {
+    fn convert_to(&self) -> Option<Element> {
+        match self {
+            Expr::Sym(Symbol::Con(x)) => {
+                Some(Element::from_str(x)).filter(Element::is_known)
+            }
+            _ => None,
+        }
+    }
+}
+/// Amounts are a number times a unit of mass (`g`, `kg`, `mg`), of amount
+/// (`mol`, `mmol`), or a volume times a molarity (`0.25 * L * 0.1 * M`).
+impl ConvertTo<Quantity> for Expr {
+    fn convert_to(&self) -> Option<Quantity> {
+        if let Some(x) = grams(self) {
+            return Some(Quantity::Grams(x))
+        }
+        if let Some(x) = moles(self) {
+            return Some(Quantity::Moles(x))
+        }
+        let (value, mut units) = unit_factors(self)?;
+        units.sort_unstable();
+        match units[..] {
+            ["L", "M"] => Some(Quantity::Moles(value)),
+            ["M", "mL"] => Some(Quantity::Moles(&value * &Number::fraction(1, 1000))),
+            _ => None,
+        }
+    }
+}
+
+/// The number and unit names of a quantity such as `250 * mL` (which is
+/// what `mL(250)` evaluates to).
+fn unit_factors(expr: &Expr) -> Option<(Number, Vec<&str>)> {
+    let factors = match expr {
+        Expr::Product(xs) => xs.iter().collect::<Vec<_>>(),
+        x => vec![x],
+    };
+    let mut value = Number::int(1);
+    let mut units = Vec::new();
+    for x in factors {
+        match x {
+            Expr::Num(x) => value = &value * &Number::from(x.clone()),
+            Expr::Sym(Symbol::Con(x)) => units.push(x.as_str()),
+            _ => return None,
+        }
+    }
+    Some((value, units))
+}
+
+/// The number and unit of a quantity, with any per-unit after a slash; e.g.
+/// `(44, "g/mol")` for `44 * g * mol⁻¹`.
+fn unit_name(expr: &Expr) -> Option<(Number, String)> {
+    let factors = match expr {
+        Expr::Product(xs) => xs.iter().collect::<Vec<_>>(),
+        x => vec![x],
+    };
+    let mut value = Number::int(1);
+    let mut numerator = Vec::new();
+    let mut denominator = Vec::new();
+    for x in factors {
+        match x {
+            Expr::Num(x) => value = &value * &Number::from(x.clone()),
+            Expr::Sym(Symbol::Con(x)) => numerator.push(x.as_str()),
+            Expr::Fraction(x) => match x.as_ref() {
+                Expr::Sym(Symbol::Con(x)) => denominator.push(x.as_str()),
+                _ => return None,
+            },
+            _ => return None,
+        }
+    }
+    let mut name = numerator.join("*");
+    if !denominator.is_empty() {
+        name.push('/');
+        name.push_str(&denominator.join("*"));
+    }
+    Some((value, name))
+}
+
+/// The quantity in terms of a single unit, given how many of that unit each
+/// accepted unit is worth.
+fn in_units(expr: &Expr, units: &[(&str, Number)]) -> Option<Number> {
+    match unit_factors(expr)? {
+        (value, names) if names.len() == 1 => {
+            let (_, factor) = units.iter().find(|(name, _)| *name == names[0])?;
+            Some(&value * factor)
+        }
+        _ => None,
+    }
+}
+
+fn liters(expr: &Expr) -> Option<Number> {
+    in_units(expr, &[("L", Number::int(1)), ("mL", Number::fraction(1, 1000))])
+}
+
+fn grams(expr: &Expr) -> Option<Number> {
+    let units = [
+        ("g", Number::int(1)),
+        ("kg", Number::int(1000)),
+        ("mg", Number::fraction(1, 1000)),
+    ];
+    in_units(expr, &units)
+}
+
+fn moles(expr: &Expr) -> Option<Number> {
+    in_units(expr, &[("mol", Number::int(1)), ("mmol", Number::fraction(1, 1000))])
+}
+
+fn molar(expr: &Expr) -> Option<Number> {
+    in_units(expr, &[("M", Number::int(1)), ("mM", Number::fraction(1, 1000))])
+}
+
+/// A concentration in `M` or `mM`, or a plain number taken to be in mol/L.
+fn concentration(expr: &Expr) -> Option<Number> {
+    match expr {
+        Expr::Num(x) => Some(x.clone().into()),
+        x => molar(x),
+    }
+}
+
+/// The analyte of a titration, e.g. `"weak acid"`; weak ones need `K`.
+fn analyte(name: &Expr, k: Option<Number>) -> Option<Analyte> {
+    let name = match name {
+        Expr::Str(x) => x.as_str(),
+        _ => return None,
+    };
+    match (name, k) {
+        ("strong acid", None) => Some(Analyte::StrongAcid),
+        ("strong base", None) => Some(Analyte::StrongBase),
+        ("weak acid", Some(ka)) => Some(Analyte::WeakAcid {ka}),
+        ("weak base", Some(kb)) => Some(Analyte::WeakBase {kb}),
+        _ => None,
+    }
+}
+
+/// The titration, with the analyte volume kept in its own unit, and the
+/// titrant volume converted to that unit.
+fn titration(
+    analyte: Analyte,
+    concentration: &Expr,
+    volume: &Expr,
+    titrant: &Expr,
+    added: &Expr,
+) -> Option<(Titration, Number, &'static str)> {
+    let (value, unit) = unit_factors(volume)?;
+    let unit = match unit[..] {
+        ["L"] => "L",
+        ["mL"] => "mL",
+        _ => return None,
+    };
+    let added = (&liters(added)? * &value).save_div(&liters(volume)?)?;
+    let titration = Titration {
+        analyte,
+        concentration: self::concentration(concentration)?,
+        volume: value,
+        titrant: self::concentration(titrant)?,
+    };
+    Some((titration, added, unit))
+}
+
+fn titration_result(titration: Titration, added: Number, unit: &str) -> Option<Expr> {
+    Some(Expr::Record(vec![
+        (String::from("pH"), Expr::Num(titration.ph(&added)?.into())),
+        (String::from("equivalence"), with_unit(titration.equivalence_volume()?, unit)),
+    ]))
+}
+
+/// The pH at each volume, keyed by the volume, along with the equivalence
+/// volume. `None` if the step is too small for a curve.
+fn titration_curve(titration: Titration, step: Number, unit: &str) -> Option<Expr> {
+    let equivalence = titration.equivalence_volume()?;
+    let until = &equivalence * &Number::int(2);
+    let points = titration
+        .curve(&step, &until)?
+        .into_iter()
+        .map(|(volume, ph)| (with_unit(volume, unit).to_string(), Expr::Num(ph.into())))
+        .collect();
+    Some(Expr::Record(vec![
+        (String::from("equivalence"), with_unit(equivalence, unit)),
+        (String::from("curve"), Expr::Record(points)),
+    ]))
+}
+
+fn with_unit(value: Number, unit: &str) -> Expr {
+    Expr::Product(vec![Expr::Num(value.into()), Expr::con(unit)])
+}
+
+/// A function such as `mL(250)`, for `250 * mL`.
+fn unit_definition(unit: &'static str) -> FunctionDecl {
+    FunctionDecl {
+        path: vec![String::from(unit)],
+        pos_args: 1,
+        key_args: Vec::new(),
+        body: Body(Rc::new(move |call: FunCall| -> Option<Expr> {
+            let value: BigRational = call.pos_args.first()?.convert_to()?;
+            Some(with_unit(value.into(), unit))
+        })),
+    }
+}
+
+/// Element symbols mapped to numbers, e.g. `{C: 40, H: 6.7, O: 53.3}`.
+fn element_masses(xs: Vec<(String, Expr)>) -> Option<Vec<(Element, Number)>> {
+    xs.into_iter()
+        .map(|(key, value)| {
+            let element = Some(Element::from_str(&key)).filter(Element::is_known)?;
+            let mass: BigRational = value.convert_to()?;
+            Some((element, Number::from(mass)))
+        })
+        .collect()
+}
+/// The gas constant in L·atm/(mol·K).
+fn gas_constant() -> Number {
+    Number::decimal("0.082057366").unwrap()
+}
+
+/// Formulas mapped to amounts, e.g. `{N2: 2 * mol, H2: 6.048 * g}`.
+fn species_quantities(xs: Vec<(String, Expr)>) -> Option<Vec<(Node, Quantity)>> {
+    xs.into_iter()
+        .map(|(key, value)| {
+            let node = Node::from_str_strict(&key)?;
+            Some((node, value.convert_to()?))
+        })
+        .collect()
+}
 
 
 
@@ -125,6 +392,145 @@ impl FunctionDecl {
     }
 }
 
+/// What a variable of a `Relation` measures, and so which units it may be
+/// given in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dimension {
+    Pressure,
+    Volume,
+    Temperature,
+    Amount,
+    Mass,
+    MolarMass,
+    Density,
+    /// A plain number, such as a mole fraction.
+    Ratio,
+}
+
+impl Dimension {
+    /// Each accepted unit, and how many of the first it's worth. Plain
+    /// numbers are taken to be in the first unit.
+    fn units(self) -> Vec<(&'static str, Number)> {
+        match self {
+            Dimension::Pressure => vec![
+                ("atm", Number::int(1)),
+                ("kPa", Number::fraction(1000, 101325)),
+                ("Pa", Number::fraction(1, 101325)),
+                ("mmHg", Number::fraction(1, 760)),
+                ("torr", Number::fraction(1, 760)),
+            ],
+            Dimension::Volume => vec![
+                ("L", Number::int(1)),
+                ("mL", Number::fraction(1, 1000)),
+            ],
+            Dimension::Temperature => vec![("K", Number::int(1))],
+            Dimension::Amount => vec![
+                ("mol", Number::int(1)),
+                ("mmol", Number::fraction(1, 1000)),
+            ],
+            Dimension::Mass => vec![
+                ("g", Number::int(1)),
+                ("kg", Number::int(1000)),
+                ("mg", Number::fraction(1, 1000)),
+            ],
+            Dimension::MolarMass => vec![("g/mol", Number::int(1))],
+            Dimension::Density => vec![
+                ("g/L", Number::int(1)),
+                ("g/mL", Number::int(1000)),
+            ],
+            Dimension::Ratio => vec![("", Number::int(1))],
+        }
+    }
+    /// The value in the first unit, and the unit it was given in.
+    fn read(self, expr: &Expr) -> Option<(Number, &'static str)> {
+        let units = self.units();
+        if let Expr::Num(x) = expr {
+            return Some((x.clone().into(), units[0].0))
+        }
+        let (value, name) = unit_name(expr)?;
+        let (unit, factor) = units.into_iter().find(|(unit, _)| *unit == name)?;
+        Some((&value * &factor, unit))
+    }
+    /// The value (in the first unit) in the given unit.
+    fn write(self, value: &Number, unit: &str) -> Option<Expr> {
+        let (_, factor) = self.units().into_iter().find(|(x, _)| *x == unit)?;
+        let value = value.save_div(&factor)?;
+        match unit.split_once('/') {
+            _ if unit.is_empty() => Some(Expr::Num(value.into())),
+            Some((numerator, denominator)) => {
+                Some(Expr::ratio(with_unit(value, numerator), Expr::con(denominator)))
+            }
+            None => Some(with_unit(value, unit)),
+        }
+    }
+}
+
+/// An equation `x₁^e₁ · x₂^e₂ ⋯ = constant`, with each exponent 1 or −1,
+/// that can be solved for whichever variable is missing. E.g. the ideal gas
+/// law `PV = nRT` is `P · V · n⁻¹ · T⁻¹ = R`.
+#[derive(Debug, Clone)]
+pub struct Relation {
+    pub name: &'static str,
+    /// The keyword, dimension and exponent of each variable.
+    pub variables: Vec<(&'static str, Dimension, i32)>,
+    pub constant: Number,
+}
+
+impl Relation {
+    /// The unknown from every other variable, in the unit of another variable
+    /// of the same dimension if there is one.
+    fn solve(&self, unknown: usize, call: &FunCall) -> Option<Expr> {
+        let only_variables = call.key_args
+            .keys()
+            .all(|key| self.variables.iter().any(|(name, _, _)| name == key));
+        if !only_variables || call.key_args.len() != self.variables.len() - 1 {
+            return None
+        }
+        let (_, dimension, exponent) = self.variables[unknown];
+        let mut product = Number::int(1);
+        let mut unit = None;
+        for (index, (name, other, power)) in self.variables.iter().enumerate() {
+            if index == unknown {
+                continue
+            }
+            let (value, given) = other.read(call.key_args.get(*name)?)?;
+            if *other == dimension && unit.is_none() {
+                unit = Some(given);
+            }
+            product = if *power > 0 {&product * &value} else {product.save_div(&value)?};
+        }
+        let value = self.constant.save_div(&product)?;
+        let value = if exponent > 0 {value} else {Number::int(1).save_div(&value)?};
+        dimension.write(&value, unit.unwrap_or(dimension.units()[0].0))
+    }
+    /// One definition per unknown, each taking every other variable as a
+    /// keyword argument. Calls with anything else, or with the unknown as
+    /// well, are left alone; so relations sharing a name (and some of their
+    /// variables) don't get in each other's way.
+    pub fn definitions(self) -> Vec<FunctionDecl> {
+        let relation = Rc::new(self);
+        (0..relation.variables.len())
+            .map(|unknown| {
+                let key_args = relation.variables
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| *index != unknown)
+                    .map(|(_, (name, _, _))| String::from(*name))
+                    .collect();
+                let relation = relation.clone();
+                FunctionDecl {
+                    path: vec![String::from(relation.name)],
+                    pos_args: 0,
+                    key_args,
+                    body: Body(Rc::new(move |call: FunCall| -> Option<Expr> {
+                        relation.solve(unknown, &call)
+                    })),
+                }
+            })
+            .collect()
+    }
+}
+
 
 ///////////////////////////////////////////////////////////////////////////////
 // MACRO DSL
@@ -134,14 +540,14 @@ impl FunctionDecl {
 #[macro_export]
 macro_rules! init_arg_header {
     ($pos_counter:expr; $keyword_state:expr; argument $name:ident : $type:ty) => {
-        $pos_counter = $pos_counter + 1;
+        $pos_counter += 1;
     };
     ($pos_counter:expr; $keyword_state:expr; keyword $name:ident : $type:ty) => {
         $keyword_state.push(String::from(stringify!($name)));
     };
 
     ($pos_counter:expr; $keyword_state:expr; argument $name:ident : $type:ty, $($rest:tt)*) => {
-        $pos_counter = $pos_counter + 1;
+        $pos_counter += 1;
         init_arg_header!($pos_counter; $keyword_state; $($rest)*)
     };
     ($pos_counter:expr; $keyword_state:expr; keyword $name:ident : $type:ty, $($rest:tt)*) => {
@@ -254,6 +660,8 @@ macro_rules! defintion {
 // FUNCTION DEFINITIONS
 ///////////////////////////////////////////////////////////////////////////////
 
+// Keyword arguments are named as in the formulas (e.g. `K`).
+#[allow(non_snake_case)]
 fn all_functions() -> Vec<FunctionDecl> {
     let mut definitions = Vec::new();
     definitions.push(defintion!(
@@ -354,6 +762,535 @@ fn all_functions() -> Vec<FunctionDecl> {
             Some(Expr::unit_fraction(frequency))
         }}
     ));
+    // NOTE:
+    // - Formula: `M = Σ nᵢAᵢ` where
+    //  * `nᵢ` is the number of atoms of each element in the formula
+    //  * `Aᵢ` is that element's standard atomic weight.
+    definitions.push(defintion!(
+        molar_mass(argument substance : Node) => {{
+            let mass = substance.molar_mass()?;
+            Some(Expr::ratio(
+                Expr::Product(vec![
+                    Expr::Num(mass.into()),
+                    Expr::con("g"),
+                ]),
+                Expr::con("mol"),
+            ))
+        }}
+    ));
+    definitions.push(defintion!(
+        formula_mass(argument substance : Node) => {{
+            let mass = substance.formula_mass()?;
+            Some(Expr::Product(vec![
+                Expr::Num(mass.into()),
+                Expr::con("u"),
+            ]))
+        }}
+    ));
+    // NOTE:
+    // - Formula: `% = 100 * nA/M` where
+    //  * `nA` is the mass contributed by the given element
+    //  * `M` is the molar mass.
+    definitions.push(defintion!(
+        percent(argument substance : Node, keyword element : Element) => {{
+            let fraction = substance
+                .percent_composition()?
+                .remove(&element)?;
+            Some(Expr::Num((fraction * Number::int(100)).into()))
+        }}
+    ));
+    // NOTE:
+    // - Moles of each element: `n = m / M`, divided by the smallest and
+    //   multiplied until every ratio is within 0.1 of a whole number.
+    // - Mass percentages are the masses in a 100 g sample.
+    // - Molecular formula: the empirical formula times
+    //   `M(molecule) / M(empirical formula)`.
+    definitions.push(defintion!(
+        empirical(keyword masses : Vec<(String, Expr)>) => {{
+            let masses = element_masses(masses)?;
+            let formula = empirical_formula(&masses).ok()?;
+            Some(Expr::con(&formula.formula()))
+        }}
+    ));
+    definitions.push(defintion!(
+        molecular(
+            keyword masses : Vec<(String, Expr)>,
+            keyword molar_mass : BigRational
+        ) => {{
+            let masses = element_masses(masses)?;
+            let empirical = empirical_formula(&masses).ok()?;
+            let formula = molecular_formula(&empirical, &molar_mass.into()).ok()?;
+            Some(Expr::con(&formula.formula()))
+        }}
+    ));
+    // NOTE:
+    // - ICE table: `[X] = [X]₀ + νx` where
+    //  * `ν` is the coefficient of `X`, negated for reactants
+    //  * `x` is the extent of reaction.
+    // - Solves `K = Π[products]^ν / Π[reactants]^ν` for `x`.
+    // - The 5% approximation holds if no species loses more than 5% of its
+    //   initial concentration.
+    definitions.push(defintion!(
+        equilibrium(
+            keyword reaction : Reaction,
+            keyword K : BigRational,
+            keyword initial : Vec<(String, Expr)>
+        ) => {{
+            let initial = initial
+                .into_iter()
+                .map(|(key, value)| {
+                    let node = Node::from_str_strict(&key)?;
+                    let value: BigRational = value.convert_to()?;
+                    Some((node, Number::from(value)))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            let table = reaction.ice_table(&K.into(), &initial).ok()?;
+            let mut results = table.species
+                .iter()
+                .zip(table.equilibrium.iter())
+                .map(|(x, c)| (concentration_symbol(x), Expr::Num(c.clone().into())))
+                .collect::<Vec<_>>();
+            let approximation = if table.approximation_is_valid() {
+                "valid"
+            } else {
+                "not valid"
+            };
+            results.push((String::from("x"), Expr::Num(table.extent.into())));
+            results.push((String::from("approximation"), Expr::Str(approximation.to_owned())));
+            Some(Expr::Record(results))
+        }}
+    ));
+    // NOTE:
+    // - Formula: `ΔX°rxn = Σ νX°(products) − Σ νX°(reactants)` where `ν` is
+    //   each coefficient, for X the enthalpy of formation or the entropy.
+    // - Gibbs energy: `ΔG° = ΔH° − TΔS°`.
+    definitions.push(defintion!(
+        enthalpy(keyword reaction : Reaction) => {{
+            reaction.enthalpy().ok()
+        }}
+    ));
+    definitions.push(defintion!(
+        entropy(keyword reaction : Reaction) => {{
+            reaction.entropy().ok()
+        }}
+    ));
+    definitions.push(defintion!(
+        gibbs(keyword reaction : Reaction, keyword T : BigRational) => {{
+            reaction.gibbs(&T.into()).ok()
+        }}
+    ));
+    // NOTE:
+    // - Hess's law: `ΔH°rxn = Σ cᵢΔH°ᵢ` where `cᵢ` is the multiple of each
+    //   step that adds up to the target (negative if reversed).
+    // - Steps map each reaction to its enthalpy change in kJ/mol.
+    definitions.push(defintion!(
+        hess(
+            keyword reaction : Reaction,
+            keyword steps : Vec<(String, Expr)>
+        ) => {{
+            let steps = steps
+                .into_iter()
+                .map(|(key, value)| {
+                    let step: Reaction = Expr::Str(key).convert_to()?;
+                    let enthalpy: BigRational = value.convert_to()?;
+                    Some((step, Number::from(enthalpy)))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            reaction.hess_enthalpy(&steps).ok().map(kilojoules_per_mole)
+        }}
+    ));
+    // NOTE:
+    // - Moles of reaction each reactant allows: `n / ν`; the smallest is
+    //   the limiting reagent.
+    // - Theoretical yield: `n(product) = ν(product) × n(reaction)`, in g.
+    // - Percent yield: `actual / theoretical × 100`.
+    definitions.push(defintion!(
+        limiting(
+            keyword reaction : Reaction,
+            keyword amounts : Vec<(String, Expr)>
+        ) => {{
+            let amounts = species_quantities(amounts)?;
+            let result = reaction.limiting_reagent(&amounts, None).ok()?;
+            let formulas = |xs: Vec<(Node, Expr)>| {
+                xs.into_iter()
+                    .map(|(x, value)| (x.formula(), value))
+                    .collect::<Vec<_>>()
+            };
+            let steps = result.steps
+                .into_iter()
+                .map(|x| (x.description, x.value))
+                .collect::<Vec<_>>();
+            Some(Expr::Record(vec![
+                (String::from("limiting"), Expr::con(&result.limiting.formula())),
+                (String::from("theoretical"), Expr::Record(formulas(result.theoretical))),
+                (String::from("leftover"), Expr::Record(formulas(result.leftover))),
+                (String::from("steps"), Expr::Record(steps)),
+            ]))
+        }}
+    ));
+    definitions.push(defintion!(
+        percent_yield(
+            keyword reaction : Reaction,
+            keyword amounts : Vec<(String, Expr)>,
+            keyword actual : Vec<(String, Expr)>
+        ) => {{
+            let amounts = species_quantities(amounts)?;
+            let actual = match &species_quantities(actual)?[..] {
+                [(product, quantity)] => (product.clone(), quantity.clone()),
+                _ => return None,
+            };
+            let result = reaction
+                .limiting_reagent(&amounts, Some((&actual.0, &actual.1)))
+                .ok()?;
+            Some(Expr::Num(result.percent_yield?.into()))
+        }}
+    ));
+    let units = [
+        "L", "mL", "g", "kg", "mg", "mol", "mmol", "M", "mM",
+        "atm", "kPa", "Pa", "mmHg", "torr", "K",
+    ];
+    for unit in units.iter() {
+        definitions.push(unit_definition(unit));
+    }
+    // NOTE:
+    // - Molarity: `M = n / V` with `V` in liters.
+    // - Molality: `m = n / kg of solvent`.
+    // - Given a substance and a mass instead of moles, `n = m / M` with `M`
+    //   the molar mass from the formula.
+    definitions.push(defintion!(
+        molarity(keyword moles : Expr, keyword volume : Expr) => {{
+            let n = self::moles(&moles)?;
+            Some(with_unit(n.save_div(&liters(&volume)?)?, "M"))
+        }}
+    ));
+    definitions.push(defintion!(
+        molarity(keyword substance : Node, keyword mass : Expr, keyword volume : Expr) => {{
+            let n = grams(&mass)?.save_div(&substance.molar_mass()?)?;
+            Some(with_unit(n.save_div(&liters(&volume)?)?, "M"))
+        }}
+    ));
+    definitions.push(defintion!(
+        molality(keyword moles : Expr, keyword solvent : Expr) => {{
+            let n = self::moles(&moles)?;
+            let kilograms = &grams(&solvent)? * &Number::fraction(1, 1000);
+            Some(Expr::ratio(with_unit(n.save_div(&kilograms)?, "mol"), Expr::con("kg")))
+        }}
+    ));
+    definitions.push(defintion!(
+        molality(keyword substance : Node, keyword mass : Expr, keyword solvent : Expr) => {{
+            let n = grams(&mass)?.save_div(&substance.molar_mass()?)?;
+            let kilograms = &grams(&solvent)? * &Number::fraction(1, 1000);
+            Some(Expr::ratio(with_unit(n.save_div(&kilograms)?, "mol"), Expr::con("kg")))
+        }}
+    ));
+    // NOTE:
+    // - Mass percent: `mass of solute / mass of solution × 100`.
+    // - Parts per million: `mass of solute / mass of solution × 10⁶`.
+    definitions.push(defintion!(
+        mass_percent(keyword solute : Expr, keyword solution : Expr) => {{
+            let fraction = grams(&solute)?.save_div(&grams(&solution)?)?;
+            Some(Expr::Num((&fraction * &Number::int(100)).into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        ppm(keyword solute : Expr, keyword solution : Expr) => {{
+            let fraction = grams(&solute)?.save_div(&grams(&solution)?)?;
+            Some(Expr::Num((&fraction * &Number::int(1000000)).into()))
+        }}
+    ));
+    // NOTE:
+    // - Dilution: `C₁V₁ = C₂V₂`, solved for whichever is missing.
+    // - Concentrations come out in M, and volumes in the unit of the other
+    //   volume.
+    definitions.push(defintion!(
+        dilution(keyword C1 : Expr, keyword V1 : Expr, keyword C2 : Expr) => {{
+            // Checked to be a volume, but kept in its own unit.
+            let (volume, unit) = unit_factors(&V1)?;
+            liters(&V1)?;
+            let value = (&molar(&C1)? * &volume).save_div(&molar(&C2)?)?;
+            Some(with_unit(value, unit[0]))
+        }}
+    ));
+    definitions.push(defintion!(
+        dilution(keyword C2 : Expr, keyword V2 : Expr, keyword C1 : Expr) => {{
+            // Checked to be a volume, but kept in its own unit.
+            let (volume, unit) = unit_factors(&V2)?;
+            liters(&V2)?;
+            let value = (&molar(&C2)? * &volume).save_div(&molar(&C1)?)?;
+            Some(with_unit(value, unit[0]))
+        }}
+    ));
+    definitions.push(defintion!(
+        dilution(keyword C1 : Expr, keyword V1 : Expr, keyword V2 : Expr) => {{
+            let value = (&molar(&C1)? * &liters(&V1)?).save_div(&liters(&V2)?)?;
+            Some(with_unit(value, "M"))
+        }}
+    ));
+    definitions.push(defintion!(
+        dilution(keyword C2 : Expr, keyword V2 : Expr, keyword V1 : Expr) => {{
+            let value = (&molar(&C2)? * &liters(&V2)?).save_div(&liters(&V1)?)?;
+            Some(with_unit(value, "M"))
+        }}
+    ));
+    // NOTE:
+    // - Mixing: `C = (C₁V₁ + C₂V₂) / (V₁ + V₂)`, assuming the volumes add.
+    definitions.push(defintion!(
+        mix(keyword C1 : Expr, keyword V1 : Expr, keyword C2 : Expr, keyword V2 : Expr) => {{
+            let (v1, v2) = (liters(&V1)?, liters(&V2)?);
+            let total = &(&molar(&C1)? * &v1) + &(&molar(&C2)? * &v2);
+            Some(with_unit(total.save_div(&(&v1 + &v2))?, "M"))
+        }}
+    ));
+    // NOTE:
+    // - `pH = −log[H⁺]`, `pOH = −log[OH⁻]` and `pH + pOH = 14` at 25 °C.
+    // - Concentrations are in M, or plain numbers in mol/L.
+    definitions.push(defintion!(
+        pH(keyword H : Expr) => {{
+            let x = acidbase::p(&concentration(&H)?)?;
+            Some(Expr::Num(x.into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        pH(keyword OH : Expr) => {{
+            let x = acidbase::complement(&acidbase::p(&concentration(&OH)?)?);
+            Some(Expr::Num(x.into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        pH(keyword pOH : BigRational) => {{
+            Some(Expr::Num(acidbase::complement(&pOH.into()).into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        pOH(keyword OH : Expr) => {{
+            let x = acidbase::p(&concentration(&OH)?)?;
+            Some(Expr::Num(x.into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        pOH(keyword H : Expr) => {{
+            let x = acidbase::complement(&acidbase::p(&concentration(&H)?)?);
+            Some(Expr::Num(x.into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        pOH(keyword pH : BigRational) => {{
+            Some(Expr::Num(acidbase::complement(&pH.into()).into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        hydronium(keyword pH : BigRational) => {{
+            Some(with_unit(acidbase::from_p(&pH.into())?, "M"))
+        }}
+    ));
+    definitions.push(defintion!(
+        hydronium(keyword pOH : BigRational) => {{
+            let pH = acidbase::complement(&pOH.into());
+            Some(with_unit(acidbase::from_p(&pH)?, "M"))
+        }}
+    ));
+    definitions.push(defintion!(
+        hydroxide(keyword pOH : BigRational) => {{
+            Some(with_unit(acidbase::from_p(&pOH.into())?, "M"))
+        }}
+    ));
+    definitions.push(defintion!(
+        hydroxide(keyword pH : BigRational) => {{
+            let pOH = acidbase::complement(&pH.into());
+            Some(with_unit(acidbase::from_p(&pOH)?, "M"))
+        }}
+    ));
+    // NOTE:
+    // - Weak acid: `Ka = x² / (C − x)` with `x = [H⁺]`, solved exactly
+    //   rather than assuming `x ≪ C`; likewise `Kb` with `x = [OH⁻]`.
+    // - Henderson–Hasselbalch: `pH = pKa + log([A⁻] / [HA])`, where the
+    //   acid and its conjugate base are both concentrations or both moles.
+    definitions.push(defintion!(
+        weak_acid(keyword C : Expr, keyword Ka : BigRational) => {{
+            let x = acidbase::weak_acid_ph(&concentration(&C)?, &Ka.into())?;
+            Some(Expr::Num(x.into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        weak_base(keyword C : Expr, keyword Kb : BigRational) => {{
+            let x = acidbase::weak_base_ph(&concentration(&C)?, &Kb.into())?;
+            Some(Expr::Num(x.into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        buffer(keyword pKa : BigRational, keyword acid : Expr, keyword base : Expr) => {{
+            let amount = |x: &Expr| concentration(x).or_else(|| moles(x));
+            let x = acidbase::buffer_ph(&pKa.into(), &amount(&acid)?, &amount(&base)?)?;
+            Some(Expr::Num(x.into()))
+        }}
+    ));
+    definitions.push(defintion!(
+        buffer(keyword Ka : BigRational, keyword acid : Expr, keyword base : Expr) => {{
+            let amount = |x: &Expr| concentration(x).or_else(|| moles(x));
+            let pKa = acidbase::p(&Ka.into())?;
+            let x = acidbase::buffer_ph(&pKa, &amount(&acid)?, &amount(&base)?)?;
+            Some(Expr::Num(x.into()))
+        }}
+    ));
+    // NOTE:
+    // - Titration of an `analyte` (`"strong acid"`, `"strong base"`, or
+    //   `"weak acid"` / `"weak base"` with `K` its Ka or Kb) by a strong
+    //   titrant, giving the pH once `added` is in, and the equivalence volume
+    //   `V = C × V(analyte) / C(titrant)` in the analyte volume's unit.
+    // - With `step` instead of `added`, the pH at every multiple of `step`
+    //   up to twice the equivalence volume, i.e. the titration curve.
+    definitions.push(defintion!(
+        titration(
+            keyword analyte : Expr,
+            keyword K : BigRational,
+            keyword concentration : Expr,
+            keyword volume : Expr,
+            keyword titrant : Expr,
+            keyword added : Expr
+        ) => {{
+            let analyte = self::analyte(&analyte, Some(K.into()))?;
+            let (titration, added, unit) = self::titration(analyte, &concentration, &volume, &titrant, &added)?;
+            titration_result(titration, added, unit)
+        }}
+    ));
+    definitions.push(defintion!(
+        titration(
+            keyword analyte : Expr,
+            keyword concentration : Expr,
+            keyword volume : Expr,
+            keyword titrant : Expr,
+            keyword added : Expr
+        ) => {{
+            let analyte = self::analyte(&analyte, None)?;
+            let (titration, added, unit) = self::titration(analyte, &concentration, &volume, &titrant, &added)?;
+            titration_result(titration, added, unit)
+        }}
+    ));
+    definitions.push(defintion!(
+        titration(
+            keyword analyte : Expr,
+            keyword K : BigRational,
+            keyword concentration : Expr,
+            keyword volume : Expr,
+            keyword titrant : Expr,
+            keyword step : Expr
+        ) => {{
+            let analyte = self::analyte(&analyte, Some(K.into()))?;
+            let (titration, step, unit) = self::titration(analyte, &concentration, &volume, &titrant, &step)?;
+            titration_curve(titration, step, unit)
+        }}
+    ));
+    definitions.push(defintion!(
+        titration(
+            keyword analyte : Expr,
+            keyword concentration : Expr,
+            keyword volume : Expr,
+            keyword titrant : Expr,
+            keyword step : Expr
+        ) => {{
+            let analyte = self::analyte(&analyte, None)?;
+            let (titration, step, unit) = self::titration(analyte, &concentration, &volume, &titrant, &step)?;
+            titration_curve(titration, step, unit)
+        }}
+    ));
+    // NOTE:
+    // - Ideal gas law: `PV = nRT`, or `PV = (m / M)RT` given a mass and
+    //   molar mass, with `R = 0.082057366 L·atm/(mol·K)`.
+    // - Combined gas law: `P₁V₁ / T₁ = P₂V₂ / T₂`.
+    // - Gas density: `d = PM / RT`, which also gives the molar mass from a
+    //   measured density.
+    // - Dalton's law: `Pᵢ = xᵢP` where `xᵢ` is the mole fraction, and the
+    //   total pressure is the sum of the partial pressures.
+    // - Each relation is solved for whichever variable is left out.
+    //   Pressures may be in atm, kPa, Pa, mmHg or torr, and temperatures
+    //   are in K; plain numbers are taken to be in atm, L, K, mol, g, g/mol
+    //   and g/L.
+    let relations = vec![
+        Relation {
+            name: "ideal_gas",
+            variables: vec![
+                ("P", Dimension::Pressure, 1),
+                ("V", Dimension::Volume, 1),
+                ("n", Dimension::Amount, -1),
+                ("T", Dimension::Temperature, -1),
+            ],
+            constant: gas_constant(),
+        },
+        Relation {
+            name: "ideal_gas",
+            variables: vec![
+                ("P", Dimension::Pressure, 1),
+                ("V", Dimension::Volume, 1),
+                ("molar_mass", Dimension::MolarMass, 1),
+                ("mass", Dimension::Mass, -1),
+                ("T", Dimension::Temperature, -1),
+            ],
+            constant: gas_constant(),
+        },
+        Relation {
+            name: "combined_gas",
+            variables: vec![
+                ("P1", Dimension::Pressure, 1),
+                ("V1", Dimension::Volume, 1),
+                ("T1", Dimension::Temperature, -1),
+                ("P2", Dimension::Pressure, -1),
+                ("V2", Dimension::Volume, -1),
+                ("T2", Dimension::Temperature, 1),
+            ],
+            constant: Number::int(1),
+        },
+        Relation {
+            name: "gas_density",
+            variables: vec![
+                ("density", Dimension::Density, 1),
+                ("T", Dimension::Temperature, 1),
+                ("P", Dimension::Pressure, -1),
+                ("molar_mass", Dimension::MolarMass, -1),
+            ],
+            constant: &Number::int(1) / &gas_constant(),
+        },
+        Relation {
+            name: "partial_pressure",
+            variables: vec![
+                ("partial", Dimension::Pressure, 1),
+                ("fraction", Dimension::Ratio, -1),
+                ("total", Dimension::Pressure, -1),
+            ],
+            constant: Number::int(1),
+        },
+    ];
+    for relation in relations {
+        definitions.extend(relation.definitions());
+    }
+    definitions.push(defintion!(
+        total_pressure(keyword partials : Vec<(String, Expr)>) => {{
+            let pressures = partials
+                .iter()
+                .map(|(_, x)| Dimension::Pressure.read(x))
+                .collect::<Option<Vec<_>>>()?;
+            let unit = pressures.first()?.1;
+            let total = pressures.into_iter().map(|(x, _)| x).sum();
+            Dimension::Pressure.write(&total, unit)
+        }}
+    ));
+    definitions.push(defintion!(
+        partial_pressure(keyword moles : Vec<(String, Expr)>, keyword total : Expr) => {{
+            let (total, unit) = Dimension::Pressure.read(&total)?;
+            let amounts = moles
+                .into_iter()
+                .map(|(gas, x)| Some((gas, Dimension::Amount.read(&x)?.0)))
+                .collect::<Option<Vec<_>>>()?;
+            let sum: Number = amounts.iter().map(|(_, x)| x.clone()).sum();
+            let pressures = amounts
+                .into_iter()
+                .map(|(gas, x)| {
+                    let partial = &x.save_div(&sum)? * &total;
+                    Some((gas, Dimension::Pressure.write(&partial, unit)?))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(Expr::Record(pressures))
+        }}
+    ));
     // // NOTE:
     // // - Formula (Rydberg formula): `1/λ = R_h * (1/n_2 - 1/n_1))` where
     // // * `R_h` is Rydberg constant
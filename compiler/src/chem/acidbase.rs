@@ -0,0 +1,256 @@
+//! pH and pOH, weak acids and bases, buffers and titration curves, all in
+//! water at 25 °C.
+use crate::numbers::Number;
+
+///////////////////////////////////////////////////////////////////////////////
+// CONSTANTS & CONVERSIONS
+///////////////////////////////////////////////////////////////////////////////
+
+/// The ion product of water, `Kw = [H⁺][OH⁻]`.
+pub fn kw() -> Number {
+    Number::decimal("1e-14").unwrap()
+}
+
+/// `pKw = pH + pOH`.
+pub fn pkw() -> Number {
+    Number::int(14)
+}
+
+/// `pX = −log X`, e.g. pH from [H⁺] or pKa from Ka. `None` unless positive.
+pub fn p(x: &Number) -> Option<Number> {
+    x.log10().map(|x| -x)
+}
+
+/// The inverse of `p`; e.g. [H⁺] from pH. `None` if out of range.
+pub fn from_p(x: &Number) -> Option<Number> {
+    (-x).exp10()
+}
+
+/// pH from pOH, or pOH from pH.
+pub fn complement(x: &Number) -> Number {
+    &pkw() - x
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// WEAK ACIDS & BASES
+///////////////////////////////////////////////////////////////////////////////
+
+/// The [H⁺] of a weak acid (or [OH⁻] of a weak base) at the given
+/// concentration, solving `K = x² / (C − x)` without the 5% approximation:
+/// `x = (−K + √(K² + 4KC)) / 2`.
+fn weak_ion(concentration: &Number, k: &Number) -> Option<Number> {
+    let discriminant = &(k * k) + &(&Number::int(4) * &(k * concentration));
+    let x = &discriminant.sqrt()? - k;
+    Some(&x / &Number::int(2))
+}
+
+pub fn weak_acid_ph(concentration: &Number, ka: &Number) -> Option<Number> {
+    p(&weak_ion(concentration, ka)?)
+}
+
+pub fn weak_base_ph(concentration: &Number, kb: &Number) -> Option<Number> {
+    Some(complement(&p(&weak_ion(concentration, kb)?)?))
+}
+
+/// Henderson–Hasselbalch: `pH = pKa + log([A⁻] / [HA])`. Amounts in moles
+/// work just as well as concentrations, since only the ratio matters.
+pub fn buffer_ph(pka: &Number, acid: &Number, base: &Number) -> Option<Number> {
+    Some(pka + &base.save_div(acid)?.log10()?)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// TITRATIONS
+///////////////////////////////////////////////////////////////////////////////
+
+/// The most points `Titration::curve` computes.
+pub const MAX_CURVE_POINTS: usize = 1000;
+
+/// What's being titrated; the titrant is a strong base for acids and a
+/// strong acid for bases.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Analyte {
+    StrongAcid,
+    StrongBase,
+    WeakAcid {ka: Number},
+    WeakBase {kb: Number},
+}
+
+impl Analyte {
+    fn is_base(&self) -> bool {
+        matches!(self, Analyte::StrongBase | Analyte::WeakBase {..})
+    }
+    /// Ka for weak acids and Kb for weak bases.
+    fn k(&self) -> Option<&Number> {
+        match self {
+            Analyte::WeakAcid {ka} => Some(ka),
+            Analyte::WeakBase {kb} => Some(kb),
+            _ => None,
+        }
+    }
+}
+
+/// Concentrations are in mol/L; volumes may be in any unit, as long as it's
+/// the same throughout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Titration {
+    pub analyte: Analyte,
+    pub concentration: Number,
+    pub volume: Number,
+    /// Concentration of the titrant.
+    pub titrant: Number,
+}
+
+impl Titration {
+    /// Volume of titrant that exactly neutralizes the analyte.
+    pub fn equivalence_volume(&self) -> Option<Number> {
+        (&self.concentration * &self.volume).save_div(&self.titrant)
+    }
+    /// pH after adding the given volume of titrant.
+    ///
+    /// Worked out in terms of the analyte's own ion (H⁺ for acids, OH⁻ for
+    /// bases), then converted to pH:
+    /// - before any titrant, the analyte alone;
+    /// - before equivalence, the excess analyte, or for weak analytes the
+    ///   buffer of what's left and its conjugate;
+    /// - at equivalence, neutral for strong analytes, otherwise the conjugate
+    ///   alone (with `K = Kw / K`);
+    /// - past equivalence, the excess titrant.
+    pub fn ph(&self, added: &Number) -> Option<Number> {
+        let analyte = &self.concentration * &self.volume;
+        let titrant = &self.titrant * added;
+        let total = &self.volume + added;
+        let zero = Number::int(0);
+        let own = if titrant < analyte {
+            let excess = &analyte - &titrant;
+            match self.analyte.k() {
+                None => p(&excess.save_div(&total)?)?,
+                Some(k) if titrant == zero => p(&weak_ion(&self.concentration, k)?)?,
+                Some(k) => buffer_ph(&p(k)?, &excess, &titrant)?,
+            }
+        } else if titrant == analyte {
+            match self.analyte.k() {
+                None => Number::int(7),
+                Some(k) => {
+                    let conjugate = analyte.save_div(&total)?;
+                    let k = kw().save_div(k)?;
+                    // In terms of the titrant's ion.
+                    complement(&p(&weak_ion(&conjugate, &k)?)?)
+                }
+            }
+        } else {
+            let excess = &titrant - &analyte;
+            complement(&p(&excess.save_div(&total)?)?)
+        };
+        if self.analyte.is_base() {
+            Some(complement(&own))
+        } else {
+            Some(own)
+        }
+    }
+    /// The pH at every multiple of `step`, from zero up to `until` (in the
+    /// same unit as the volume). `None` unless the step is positive and
+    /// gives at most `MAX_CURVE_POINTS` points.
+    pub fn curve(&self, step: &Number, until: &Number) -> Option<Vec<(Number, Number)>> {
+        let mut points = Vec::new();
+        let mut added = Number::int(0);
+        if *step <= Number::int(0) || until.save_div(step)? > Number::from_usize(MAX_CURVE_POINTS) {
+            return None
+        }
+        while added <= *until {
+            if let Some(ph) = self.ph(&added) {
+                points.push((added.clone(), ph));
+            }
+            added = &added + step;
+        }
+        Some(points)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(x: &str) -> Number {
+        Number::decimal(x).unwrap()
+    }
+
+    fn approx(x: Option<Number>, expected: f64) {
+        let x = x.unwrap().to_f64().unwrap();
+        assert!((x - expected).abs() < 1e-3, "{} != {}", x, expected);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(p(&number("0.001")), Some(Number::int(3)));
+        assert_eq!(from_p(&Number::int(3)), Some(number("0.001")));
+        assert_eq!(complement(&Number::int(3)), Number::int(11));
+        approx(p(&number("2.5e-4")), 3.60206);
+        approx(p(&from_p(&number("4.5")).unwrap()), 4.5);
+        assert_eq!(from_p(&number("-400.5")), None);
+        assert_eq!(from_p(&Number::int(-100000)), None);
+        assert_eq!(p(&Number::int(0)), None);
+    }
+
+    #[test]
+    fn weak_acids_and_bases() {
+        approx(weak_acid_ph(&number("0.1"), &number("1.8e-5")), 2.8753);
+        approx(weak_base_ph(&number("0.1"), &number("1.8e-5")), 11.1247);
+        assert_eq!(
+            buffer_ph(&number("4.74"), &number("0.1"), &number("0.1")),
+            Some(number("4.74"))
+        );
+        approx(buffer_ph(&number("4.74"), &number("0.1"), &number("0.2")), 5.04103);
+    }
+
+    #[test]
+    fn titrations() {
+        // 25 mL of 0.1 M acetic acid with 0.1 M NaOH.
+        let acetic = Titration {
+            analyte: Analyte::WeakAcid {ka: number("1.8e-5")},
+            concentration: number("0.1"),
+            volume: Number::int(25),
+            titrant: number("0.1"),
+        };
+        assert_eq!(acetic.equivalence_volume(), Some(Number::int(25)));
+        approx(acetic.ph(&Number::int(0)), 2.8753);
+        approx(acetic.ph(&number("12.5")), 4.74473);
+        approx(acetic.ph(&Number::int(25)), 8.72185);
+        approx(acetic.ph(&Number::int(35)), 12.22185);
+        // 25 mL of 0.1 M HCl with 0.1 M NaOH.
+        let hydrochloric = Titration {
+            analyte: Analyte::StrongAcid,
+            ..acetic.clone()
+        };
+        approx(hydrochloric.ph(&Number::int(10)), 1.36798);
+        assert_eq!(hydrochloric.ph(&Number::int(25)), Some(Number::int(7)));
+        // 20 mL of 0.1 M NaOH with 0.2 M HCl.
+        let hydroxide = Titration {
+            analyte: Analyte::StrongBase,
+            concentration: number("0.1"),
+            volume: Number::int(20),
+            titrant: number("0.2"),
+        };
+        assert_eq!(hydroxide.equivalence_volume(), Some(Number::int(10)));
+        assert_eq!(hydroxide.ph(&Number::int(0)), Some(Number::int(13)));
+        approx(hydroxide.ph(&Number::int(15)), 1.54407);
+        // 20 mL of 0.1 M ammonia with 0.2 M HCl.
+        let ammonia = Titration {
+            analyte: Analyte::WeakBase {kb: number("1.8e-5")},
+            ..hydroxide
+        };
+        approx(ammonia.ph(&Number::int(5)), 9.25527);
+        approx(ammonia.ph(&Number::int(10)), 5.2157);
+        let curve = acetic.curve(&Number::int(5), &Number::int(50)).unwrap();
+        assert_eq!(curve.len(), 11);
+        assert!(curve.windows(2).all(|x| x[0].1 < x[1].1));
+        assert_eq!(acetic.curve(&number("0.05"), &Number::int(50)).map(|x| x.len()), Some(1001));
+        assert_eq!(acetic.curve(&number("1e-7"), &Number::int(50)), None);
+        assert_eq!(acetic.curve(&Number::int(0), &Number::int(50)), None);
+    }
+}
@@ -0,0 +1,258 @@
+//! Arithmetic on reactions, and Hess's law.
+use crate::numbers::Number;
+use crate::matrix::Matrix;
+use crate::chem::data::*;
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HessError {
+    /// No combination of the given reactions adds up to the target.
+    NoCombination,
+    /// More than one combination does, since some of the given reactions are
+    /// combinations of the others.
+    Underdetermined,
+}
+
+impl std::fmt::Display for HessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HessError::NoCombination => {
+                write!(f, "the target is not a combination of the given reactions")
+            }
+            HessError::Underdetermined => {
+                write!(f, "the given reactions combine into the target in more than one way")
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// TERMS
+///////////////////////////////////////////////////////////////////////////////
+
+/// The same species in the same state, however the formula is written; so
+/// `H2O(l)` and `H2O(g)` are different terms.
+pub fn is_same_term(left: &Node, right: &Node) -> bool {
+    left.is_same_species(right) && left.state() == right.state()
+}
+
+impl Sequence {
+    /// Adds the node, combining it with the same term if there is one.
+    pub fn add_term(&mut self, node: Node) {
+        match self.0.iter_mut().find(|x| is_same_term(x, &node)) {
+            Some(x) => {
+                let total = &x.root_coefficient() + &node.root_coefficient();
+                *x = x.clone().with_coefficient(total);
+            }
+            None => self.0.push(node),
+        }
+    }
+    /// The total coefficient of the term, or zero if it's absent.
+    pub fn coefficient_of(&self, node: &Node) -> Number {
+        self.0
+            .iter()
+            .filter(|x| is_same_term(x, node))
+            .map(Node::root_coefficient)
+            .sum()
+    }
+    fn scale(&self, factor: &Number) -> Sequence {
+        let xs = self.0
+            .iter()
+            .map(|x| x.clone().with_coefficient(&x.root_coefficient() * factor))
+            .collect();
+        Sequence(xs)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// REACTION ALGEBRA
+///////////////////////////////////////////////////////////////////////////////
+
+impl Reaction {
+    /// Swaps the reactants and products.
+    pub fn reverse(&self) -> Reaction {
+        Reaction {
+            reactants: self.products.clone(),
+            products: self.reactants.clone(),
+            ..self.clone()
+        }
+    }
+    /// Multiplies every coefficient; a negative factor also reverses the
+    /// reaction.
+    pub fn scale(&self, factor: &Number) -> Reaction {
+        let reaction = if *factor < Number::int(0) {self.reverse()} else {self.clone()};
+        let factor = factor.abs();
+        Reaction {
+            reactants: reaction.reactants.scale(&factor),
+            products: reaction.products.scale(&factor),
+            ..reaction
+        }
+    }
+    /// Removes whatever appears on both sides, leaving the difference on the
+    /// side that had more. Terms with a zero coefficient are dropped.
+    pub fn cancel(&self) -> Reaction {
+        let mut reactants = Sequence(Vec::new());
+        let mut products = Sequence(Vec::new());
+        for x in self.reactants.0.iter() {
+            let net = &x.root_coefficient() - &self.products.coefficient_of(x);
+            if net > Number::int(0) {
+                reactants.add_term(x.clone().with_coefficient(net));
+            }
+        }
+        for x in self.products.0.iter() {
+            let net = &x.root_coefficient() - &self.reactants.coefficient_of(x);
+            if net > Number::int(0) {
+                products.add_term(x.clone().with_coefficient(net));
+            }
+        }
+        Reaction {
+            reactants,
+            products,
+            ..self.clone()
+        }
+    }
+    /// Net coefficient of each term, products positive and reactants
+    /// negative.
+    fn term_coefficients(&self, terms: &[Node]) -> Vec<Number> {
+        terms
+            .iter()
+            .map(|x| &self.products.coefficient_of(x) - &self.reactants.coefficient_of(x))
+            .collect()
+    }
+    /// Hess's law: the multiple of each step such that the steps add up to
+    /// this reaction (once species on both sides cancel). A negative
+    /// multiple means the step is reversed.
+    pub fn hess_combination(&self, steps: &[Reaction]) -> Result<Vec<Number>, HessError> {
+        let mut terms = Sequence(Vec::new());
+        for reaction in steps.iter().chain(std::iter::once(self)) {
+            for x in reaction.merge().0 {
+                terms.add_term(x);
+            }
+        }
+        // One row per term and one column per step, augmented with the
+        // target.
+        let columns = steps
+            .iter()
+            .chain(std::iter::once(self))
+            .map(|x| x.term_coefficients(&terms.0))
+            .collect::<Vec<_>>();
+        let rows = (0..terms.0.len())
+            .map(|row| columns.iter().map(|col| col[row].clone()).collect())
+            .collect::<Vec<_>>();
+        let matrix = Matrix::from_rows(rows).ok_or(HessError::NoCombination)?;
+        match matrix.solve() {
+            Some(solution) => Ok(solution.0),
+            // A pivot in the target's column means no combination works;
+            // otherwise there is more than one.
+            None if matrix.pivot_columns().contains(&steps.len()) => {
+                Err(HessError::NoCombination)
+            }
+            None => Err(HessError::Underdetermined),
+        }
+    }
+    /// The enthalpy change of this reaction from steps with known enthalpy
+    /// changes, by Hess's law.
+    pub fn hess_enthalpy(&self, steps: &[(Reaction, Number)]) -> Result<Number, HessError> {
+        let reactions = steps.iter().map(|(x, _)| x.clone()).collect::<Vec<_>>();
+        let multiples = self.hess_combination(&reactions)?;
+        Ok(multiples
+            .iter()
+            .zip(steps.iter())
+            .map(|(multiple, (_, enthalpy))| multiple * enthalpy)
+            .sum())
+    }
+}
+
+impl std::ops::Add for &Reaction {
+    type Output = Reaction;
+    /// Both sides added together, without cancelling.
+    fn add(self, other: Self) -> Reaction {
+        let mut reactants = self.reactants.clone();
+        let mut products = self.products.clone();
+        for x in other.reactants.0.iter() {
+            reactants.add_term(x.clone());
+        }
+        for x in other.products.0.iter() {
+            products.add_term(x.clone());
+        }
+        Reaction {
+            reactants,
+            products,
+            ..self.clone()
+        }
+    }
+}
+impl std::ops::Mul<&Number> for &Reaction {
+    type Output = Reaction;
+    fn mul(self, factor: &Number) -> Reaction {self.scale(factor)}
+}
+impl std::ops::Neg for &Reaction {
+    type Output = Reaction;
+    fn neg(self) -> Reaction {self.reverse()}
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(reaction: &Reaction) -> String {
+        let side = |xs: &Sequence| {
+            xs.0.iter()
+                .map(|x| format!("{} {}", x.root_coefficient(), x.formula()))
+                .collect::<Vec<_>>()
+                .join(" + ")
+        };
+        format!("{} -> {}", side(&reaction.reactants), side(&reaction.products))
+    }
+
+    #[test]
+    fn arithmetic() {
+        let reaction = |x: &str| Reaction::from_str(x).unwrap();
+        let combustion = reaction("CH4 + 2O2 -> CO2 + 2H2O");
+        assert_eq!(render(&-&combustion), "1 CO2 + 2 H2O -> 1 CH4 + 2 O2");
+        assert_eq!(render(&(&combustion * &Number::fraction(1, 2))), "1/2 CH4 + 1 O2 -> 1/2 CO2 + 1 H2O");
+        assert_eq!(render(&(&combustion * &Number::int(-2))), "2 CO2 + 4 H2O -> 2 CH4 + 4 O2");
+        let sum = &reaction("C + O2 -> CO2") + &reaction("2CO2 -> 2CO + O2");
+        assert_eq!(render(&sum), "1 C + 1 O2 + 2 CO2 -> 1 CO2 + 2 CO + 1 O2");
+        assert_eq!(render(&sum.cancel()), "1 C + 1 CO2 -> 2 CO");
+        // Formulas are compared by composition, and states must match.
+        let written = reaction("CH3COOH(aq) + H2O(l) -> C2H4O2(aq) + H2O(g)").cancel();
+        assert_eq!(render(&written), "1 H2O -> 1 H2O");
+    }
+
+    #[test]
+    fn hess() {
+        let reaction = |x: &str| Reaction::from_str(x).unwrap();
+        let target = reaction("C(s) + 2H2(g) -> CH4(g)");
+        let steps = [
+            (reaction("C(s) + O2(g) -> CO2(g)"), Number::decimal("-393.5").unwrap()),
+            (reaction("H2(g) + 0.5O2(g) -> H2O(l)"), Number::decimal("-285.8").unwrap()),
+            (reaction("CH4(g) + 2O2(g) -> CO2(g) + 2H2O(l)"), Number::decimal("-890.3").unwrap()),
+        ];
+        let reactions = steps.iter().map(|(x, _)| x.clone()).collect::<Vec<_>>();
+        let multiples = target.hess_combination(&reactions).unwrap();
+        assert_eq!(multiples, vec![Number::int(1), Number::int(2), Number::int(-1)]);
+        let combined = reactions
+            .iter()
+            .zip(multiples.iter())
+            .map(|(x, n)| x * n)
+            .fold(Reaction::new(Sequence(vec![]), Sequence(vec![])), |acc, x| &acc + &x)
+            .cancel();
+        assert_eq!(render(&combined), render(&target));
+        assert_eq!(target.hess_enthalpy(&steps).unwrap(), Number::decimal("-74.8").unwrap());
+        let unrelated = reaction("N2(g) + 3H2(g) -> 2NH3(g)");
+        assert_eq!(unrelated.hess_combination(&reactions), Err(HessError::NoCombination));
+        let doubled = [reactions[0].clone(), &reactions[0] * &Number::int(2)];
+        let error = reaction("C(s) + O2(g) -> CO2(g)").hess_combination(&doubled);
+        assert_eq!(error, Err(HessError::Underdetermined));
+    }
+}
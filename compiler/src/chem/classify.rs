@@ -0,0 +1,311 @@
+//! Reaction types, and products predicted from the reactants.
+use crate::numbers::Number;
+use crate::chem::data::*;
+use crate::chem::ionic::{compound, dissociate, is_soluble};
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactionType {
+    Combustion,
+    Synthesis,
+    Decomposition,
+    SingleReplacement,
+    /// Including precipitation reactions.
+    DoubleReplacement,
+    Neutralization,
+    /// Any other reaction where oxidation states change.
+    Redox,
+}
+
+impl std::fmt::Display for ReactionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReactionType::Combustion => write!(f, "combustion"),
+            ReactionType::Synthesis => write!(f, "synthesis"),
+            ReactionType::Decomposition => write!(f, "decomposition"),
+            ReactionType::SingleReplacement => write!(f, "single replacement"),
+            ReactionType::DoubleReplacement => write!(f, "double replacement"),
+            ReactionType::Neutralization => write!(f, "acid–base neutralization"),
+            ReactionType::Redox => write!(f, "redox"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PredictionError {
+    /// The reactants don't fit any of the reaction types.
+    Unknown,
+    /// Products are only predicted for combustion, double replacement and
+    /// neutralization.
+    Unsupported(ReactionType),
+    /// A reactant of an ion swap that doesn't split into a cation and anion.
+    NotIonic(Node),
+    Unbalanced(BalanceError),
+}
+
+impl std::fmt::Display for PredictionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PredictionError::Unknown => {
+                write!(f, "unknown reaction type")
+            }
+            PredictionError::Unsupported(x) => {
+                write!(f, "cannot predict the products of a {} reaction", x)
+            }
+            PredictionError::NotIonic(x) => {
+                write!(f, "cannot split {} into ions", x.to_string())
+            }
+            PredictionError::Unbalanced(error) => {
+                write!(f, "cannot balance the predicted reaction: {}", error)
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// HELPERS
+///////////////////////////////////////////////////////////////////////////////
+
+fn species(source: &str) -> Node {
+    Node::from_str(source).unwrap()
+}
+
+fn is_element(node: &Node) -> bool {
+    node.charge() == 0 && node.composition().elements().len() == 1
+}
+
+/// Made up of carbon, hydrogen and oxygen only, and not CO₂ itself.
+fn is_fuel(node: &Node) -> bool {
+    let elements = node.composition().elements();
+    let allowed = ["C", "H", "O"].iter().map(|x| Element::from_str(x)).collect::<Vec<_>>();
+    elements.contains(&Element::from_str("C")) &&
+    elements.iter().all(|x| allowed.contains(x)) &&
+    !node.is_same_species(&species("CO2"))
+}
+
+fn is_acid(node: &Node) -> bool {
+    match dissociate(node) {
+        Some((cation, anion)) => {
+            cation.is_same_species(&species("H^+")) &&
+            !anion.is_same_species(&species("O^2-")) &&
+            !anion.is_same_species(&species("OH^-"))
+        }
+        None => false,
+    }
+}
+
+fn is_base(node: &Node) -> bool {
+    match dissociate(node) {
+        Some((_, anion)) => anion.is_same_species(&species("OH^-")),
+        None => false,
+    }
+}
+
+fn is_ionic(node: &Node) -> bool {
+    dissociate(node).is_some()
+}
+
+fn either(xs: [&Node; 2], f: impl Fn(&Node) -> bool, g: impl Fn(&Node) -> bool) -> bool {
+    (f(xs[0]) && g(xs[1])) || (g(xs[0]) && f(xs[1]))
+}
+
+fn without_electrons(xs: &Sequence) -> Vec<&Node> {
+    xs.0.iter().filter(|x| !x.is_electron()).collect()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// CLASSIFICATION
+///////////////////////////////////////////////////////////////////////////////
+
+impl Sequence {
+    /// The type of reaction these reactants undergo, where it follows from
+    /// the reactants alone.
+    pub fn reaction_type(&self) -> Option<ReactionType> {
+        match &without_electrons(self)[..] {
+            [_] => Some(ReactionType::Decomposition),
+            [a, b] => {
+                let xs = [*a, *b];
+                let oxygen = |x: &Node| x.is_same_species(&species("O2"));
+                if either(xs, oxygen, is_fuel) {
+                    Some(ReactionType::Combustion)
+                } else if either(xs, is_acid, is_base) {
+                    Some(ReactionType::Neutralization)
+                } else if is_ionic(a) && is_ionic(b) {
+                    Some(ReactionType::DoubleReplacement)
+                } else if either(xs, is_element, is_ionic) {
+                    Some(ReactionType::SingleReplacement)
+                } else if is_element(a) && is_element(b) {
+                    Some(ReactionType::Synthesis)
+                } else {
+                    None
+                }
+            }
+            xs if xs.len() > 2 && xs.iter().all(|x| is_element(x)) => {
+                Some(ReactionType::Synthesis)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Reaction {
+    /// The most specific type that fits, in the order combustion,
+    /// neutralization, synthesis, decomposition, single replacement and
+    /// double replacement; otherwise redox if any oxidation state changes.
+    pub fn reaction_type(&self) -> Option<ReactionType> {
+        let reactants = without_electrons(&self.reactants);
+        let products = without_electrons(&self.products);
+        let contains = |xs: &[&Node], formula: &str| {
+            xs.iter().any(|x| x.is_same_species(&species(formula)))
+        };
+        match self.reactants.reaction_type() {
+            Some(ReactionType::Combustion) if contains(&products, "CO2") => {
+                return Some(ReactionType::Combustion)
+            }
+            Some(ReactionType::Neutralization) if contains(&products, "H2O") => {
+                return Some(ReactionType::Neutralization)
+            }
+            _ => {}
+        }
+        match (&reactants[..], &products[..]) {
+            ([_, _, ..], [_]) => return Some(ReactionType::Synthesis),
+            ([_], [_, _, ..]) => return Some(ReactionType::Decomposition),
+            ([a, b], [c, d]) => {
+                let single = |x: [&Node; 2]| either(x, is_element, is_ionic);
+                if single([a, b]) && single([c, d]) {
+                    return Some(ReactionType::SingleReplacement)
+                }
+                if [a, b, c, d].iter().all(|x| is_ionic(x)) {
+                    return Some(ReactionType::DoubleReplacement)
+                }
+            }
+            _ => {}
+        }
+        match self.oxidation_changes() {
+            Ok(changes) if !changes.is_empty() => Some(ReactionType::Redox),
+            _ => None,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// PRODUCT PREDICTION
+///////////////////////////////////////////////////////////////////////////////
+
+impl Sequence {
+    /// Completes and balances the reaction for combustion (to CO₂ and H₂O),
+    /// double replacement and neutralization (by swapping ions). Products of
+    /// ion swaps are given states by the solubility rules, so precipitates
+    /// are `(s)`.
+    pub fn predict_products(&self) -> Result<Reaction, PredictionError> {
+        let kind = self.reaction_type().ok_or(PredictionError::Unknown)?;
+        let reactants = self.formula_units();
+        let products = match kind {
+            ReactionType::Combustion => {
+                let has_hydrogen = reactants.0
+                    .iter()
+                    .any(|x| is_fuel(x) && !x.composition().get(&Element::from_str("H")).is_zero());
+                let mut xs = vec![species("CO2(g)")];
+                if has_hydrogen {
+                    xs.push(species("H2O(g)"));
+                }
+                xs
+            }
+            ReactionType::DoubleReplacement | ReactionType::Neutralization => {
+                let ions = |x: &Node| {
+                    dissociate(x).ok_or_else(|| PredictionError::NotIonic(x.clone()))
+                };
+                let (c1, a1, c2, a2) = match &without_electrons(&reactants)[..] {
+                    [a, b] => {
+                        let (c1, a1) = ions(a)?;
+                        let (c2, a2) = ions(b)?;
+                        (c1, a1, c2, a2)
+                    }
+                    _ => return Err(PredictionError::Unknown),
+                };
+                let product = |cation: &Node, anion: &Node| {
+                    let node = compound(cation, anion);
+                    let state = if node.is_same_species(&species("H2O")) {
+                        State::L
+                    } else if cation.is_same_species(&species("H^+")) || is_soluble(cation, anion) {
+                        State::Aq
+                    } else {
+                        State::S
+                    };
+                    node.with_state(Some(state))
+                };
+                vec![product(&c1, &a2), product(&c2, &a1)]
+            }
+            kind => return Err(PredictionError::Unsupported(kind)),
+        };
+        Reaction::new(reactants, Sequence(products))
+            .balance()
+            .map_err(PredictionError::Unbalanced)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reaction_types() {
+        let samples = [
+            ("CH4 + 2O2 -> CO2 + 2H2O", ReactionType::Combustion),
+            ("HCl + NaOH -> NaCl + H2O", ReactionType::Neutralization),
+            ("2H2 + O2 -> 2H2O", ReactionType::Synthesis),
+            ("2H2O2 -> 2H2O + O2", ReactionType::Decomposition),
+            ("Zn + CuSO4 -> ZnSO4 + Cu", ReactionType::SingleReplacement),
+            ("AgNO3 + NaCl -> AgCl + NaNO3", ReactionType::DoubleReplacement),
+            ("Fe^2+ + Ce^4+ -> Fe^3+ + Ce^3+", ReactionType::Redox),
+        ];
+        for (source, expected) in samples.iter() {
+            let reaction = Reaction::from_str(source).unwrap();
+            assert_eq!(reaction.reaction_type(), Some(*expected), "{}", source);
+        }
+        let reactants = |x: &str| Sequence::from_str(x).unwrap();
+        assert_eq!(reactants("C3H8 + O2").reaction_type(), Some(ReactionType::Combustion));
+        assert_eq!(reactants("Mg + HCl").reaction_type(), Some(ReactionType::SingleReplacement));
+        assert_eq!(reactants("Na + Cl2").reaction_type(), Some(ReactionType::Synthesis));
+        assert_eq!(reactants("CH4 + NH3").reaction_type(), None);
+    }
+
+    #[test]
+    fn predicted_products() {
+        let run = |source: &str| {
+            let reaction = Sequence::from_str(source).unwrap().predict_products().unwrap();
+            assert!(reaction.is_balanced());
+            let side = |xs: &Sequence| {
+                xs.0.iter()
+                    .map(|x| format!("{}{}", x.root_coefficient(), x.formula()))
+                    .collect::<Vec<_>>()
+                    .join(" + ")
+            };
+            format!("{} -> {}", side(&reaction.reactants), side(&reaction.products))
+        };
+        assert_eq!(run("C3H8 + O2"), "1C3H8 + 5O2 -> 3CO2 + 4H2O");
+        assert_eq!(run("C + O2"), "1C + 1O2 -> 1CO2");
+        assert_eq!(run("Pb(NO3)2 + KI"), "1Pb(NO3)2 + 2KI -> 1PbI2 + 2KNO3");
+        assert_eq!(run("H2SO4 + NaOH"), "1H2SO4 + 2NaOH -> 2H2O + 1Na2SO4");
+        assert_eq!(run("Ca(OH)2 + HNO3"), "1Ca(OH)2 + 2HNO3 -> 1Ca(NO3)2 + 2H2O");
+        assert_eq!(run("(NH4)2SO4 + BaCl2"), "1(NH4)2SO4 + 1BaCl2 -> 2NH4Cl + 1BaSO4");
+        let precipitate = Sequence::from_str("AgNO3 + NaCl").unwrap().predict_products().unwrap();
+        assert_eq!(precipitate.products.0[0].formula(), "AgCl");
+        assert_eq!(precipitate.products.0[0].state(), Some(&State::S));
+        assert_eq!(precipitate.products.0[1].state(), Some(&State::Aq));
+        let error = Sequence::from_str("Zn + CuSO4").unwrap().predict_products();
+        assert!(matches!(error, Err(PredictionError::Unsupported(ReactionType::SingleReplacement))));
+        // Free electrons are skipped when swapping ions.
+        let error = Sequence::from_str("AgNO3 + e- + NaCl").unwrap().predict_products();
+        assert!(matches!(error, Err(PredictionError::Unbalanced(_))), "{:?}", error);
+    }
+}
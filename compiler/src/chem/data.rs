@@ -2,10 +2,11 @@ use std::iter::FromIterator;
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 use std::cell::{RefCell, Cell};
-use std::collections::{HashMap, BTreeSet, BTreeMap, LinkedList};
+use std::collections::{HashMap, HashSet, BTreeSet, BTreeMap, LinkedList};
 
 use crate::numbers::Number;
 use crate::matrix::{Matrix, Row, Column};
+use crate::chem::periodic::ElementInfo;
 
 ///////////////////////////////////////////////////////////////////////////////
 // BASICS
@@ -13,6 +14,8 @@ use crate::matrix::{Matrix, Row, Column};
 
 type Coefficient = Number;
 type Subscript = Number;
+/// Net ionic charge of a formula unit, in multiples of the elementary charge.
+pub type Charge = isize;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct Element(pub String);
@@ -21,6 +24,17 @@ impl Element {
     pub fn from_str(value: &str) -> Self {
         Element(String::from(value))
     }
+    /// Periodic table entry, if this is a real element.
+    pub fn info(&self) -> Option<&'static ElementInfo> {
+        ElementInfo::from_symbol(&self.0)
+    }
+    pub fn is_known(&self) -> bool {
+        self.info().is_some()
+    }
+    /// Standard atomic weight in g/mol.
+    pub fn atomic_weight(&self) -> Option<Number> {
+        self.info().map(ElementInfo::atomic_weight)
+    }
 }
 
 impl std::fmt::Display for Element {
@@ -29,7 +43,71 @@ impl std::fmt::Display for Element {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The kind of brackets around a group, kept so formulas render as written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bracket {
+    Round,
+    Square,
+}
+
+/// Number of atoms of each element. Counts are exact, so fractional
+/// subscripts are fine.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Composition(pub BTreeMap<Element, Number>);
+
+impl Composition {
+    /// Zero if the element is absent.
+    pub fn get(&self, element: &Element) -> Number {
+        self.0.get(element).cloned().unwrap_or(Number::int(0))
+    }
+    pub fn elements(&self) -> Vec<Element> {
+        self.0.keys().cloned().collect()
+    }
+    pub fn add(&mut self, element: &Element, count: &Number) {
+        let total = &self.get(element) + count;
+        self.0.insert(element.clone(), total);
+    }
+    /// Adds `mult` copies of `other`.
+    pub fn add_scaled(&mut self, other: &Composition, mult: &Number) {
+        for (element, count) in other.0.iter() {
+            self.add(element, &(count * mult));
+        }
+    }
+    /// Elements with a non-zero count in Hill order: carbon first, then
+    /// hydrogen, then the rest alphabetically. Without carbon, every element
+    /// (hydrogen included) is alphabetical.
+    pub fn hill_order(&self) -> Vec<(Element, Number)> {
+        let mut xs = self.0
+            .iter()
+            .filter(|(_, count)| !count.is_zero())
+            .map(|(element, count)| (element.clone(), count.clone()))
+            .collect::<Vec<_>>();
+        if xs.iter().any(|(x, _)| x.0 == "C") {
+            let rank = |x: &Element| match x.0.as_str() {
+                "C" => 0,
+                "H" => 1,
+                _ => 2,
+            };
+            xs.sort_by(|(l, _), (r, _)| rank(l).cmp(&rank(r)).then(l.cmp(r)));
+        }
+        xs
+    }
+    /// The Hill-system formula, e.g. `C2H4O2` for acetic acid.
+    pub fn hill_formula(&self) -> String {
+        self.hill_order()
+            .into_iter()
+            .map(|(element, count)| {
+                if count == Number::int(1) {
+                    element.0
+                } else {
+                    format!("{}{}", element, render_number(&count))
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum State {
     Aq,
     S,
@@ -42,41 +120,82 @@ pub enum State {
 // SUBSTANCE
 ///////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, Clone)]
+/// Decimals where exact (`0.95`), otherwise fractions (`1/3`).
+fn render_number(x: &Number) -> String {
+    x.to_exact_decimal().unwrap_or_else(|| {
+        format!("{}/{}", x.numerator().unwrap(), x.denominator().unwrap())
+    })
+}
+
+fn hill_units(composition: &Composition) -> Vec<Node> {
+    composition
+        .hill_order()
+        .into_iter()
+        .map(|(element, count)| Node::Unit(element, count))
+        .collect()
+}
+
+/// Equality and hashing are structural, so `HOH` and `H2O` differ; compare
+/// `canonical` forms (or use `is_same_species`) to go by composition.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Node {
-    Chunk(Coefficient, Vec<Node>, Option<State>),
-    Parens(Vec<Node>, Subscript),
+    /// A free electron (`e⁻`) is a chunk without any atoms and a charge of −1.
+    Chunk(Coefficient, Vec<Node>, Charge, Option<State>),
+    Parens(Vec<Node>, Subscript, Bracket),
     Unit(Element, Subscript),
+    /// Dot-joined parts of an adduct or hydrate, each with its own multiplier
+    /// (e.g. `CuSO₄·5H₂O`).
+    Adduct(Vec<(Subscript, Vec<Node>)>),
 }
 
 impl Node {
     pub fn to_string(&self) -> String {
         fn fancy_unicode_subscript(x: &Number) -> String {
-            let sign = x.sign();
-            let num = x.numerator().unwrap().abs();
-            let den = x.denominator().unwrap().abs();
-            if den == 1 {
-                format!("{}", num)
+            render_number(x)
+                .chars()
+                .map(|x: char| {
+                    match x {
+                        '0' => '₀',
+                        '1' => '₁',
+                        '2' => '₂',
+                        '3' => '₃',
+                        '4' => '₄',
+                        '5' => '₅',
+                        '6' => '₆',
+                        '7' => '₇',
+                        '8' => '₈',
+                        '9' => '₉',
+                        '/' => '⁄',
+                        x => x,
+                    }
+                })
+                .collect::<String>()
+        }
+        fn fancy_unicode_superscript(charge: Charge) -> String {
+            let sign = if charge < 0 {'⁻'} else {'⁺'};
+            let digits = match charge.abs() {
+                0 => return String::new(),
+                1 => String::new(),
+                x => format!("{}", x)
                     .chars()
                     .map(|x: char| {
                         match x {
-                            '0' => '₀',
-                            '1' => '₁',
-                            '2' => '₂',
-                            '3' => '₃',
-                            '4' => '₄',
-                            '5' => '₅',
-                            '6' => '₆',
-                            '7' => '₇',
-                            '8' => '₈',
-                            '9' => '₉',
+                            '0' => '⁰',
+                            '1' => '¹',
+                            '2' => '²',
+                            '3' => '³',
+                            '4' => '⁴',
+                            '5' => '⁵',
+                            '6' => '⁶',
+                            '7' => '⁷',
+                            '8' => '⁸',
+                            '9' => '⁹',
                             x => x,
                         }
                     })
-                    .collect::<String>()
-            } else {
-                unimplemented!()
-            }
+                    .collect::<String>(),
+            };
+            format!("{}{}", digits, sign)
         }
         fn render_list(xs: &Vec<Node>) -> String {
             xs.into_iter()
@@ -93,102 +212,289 @@ impl Node {
             }
         }
         match self {
-            Node::Chunk(coefficient, xs, state_opt) => {
-                let xs = render_list(xs);
+            Node::Chunk(coefficient, xs, charge, state_opt) => {
+                let xs = if xs.is_empty() {
+                    String::from("e")
+                } else {
+                    render_list(xs)
+                };
+                let charge = fancy_unicode_superscript(*charge);
                 let state = state_opt.as_ref().map(render_state).unwrap_or(String::new());
-                format!("{}{} {}", coefficient, xs, state)
+                // A space keeps a fractional coefficient apart from the
+                // subscripts that follow.
+                let coefficient = if coefficient.is_integer() {
+                    coefficient.to_string()
+                } else {
+                    format!("{} ", render_number(coefficient))
+                };
+                format!("{}{}{} {}", coefficient, xs, charge, state)
             }
-            Node::Parens(xs, subscritp) => {
+            Node::Parens(xs, subscritp, bracket) => {
                 let xs = render_list(xs);
-                format!("({}){}", xs, fancy_unicode_subscript(&subscritp))
+                let (open, close) = match bracket {
+                    Bracket::Round => ('(', ')'),
+                    Bracket::Square => ('[', ']'),
+                };
+                format!("{}{}{}{}", open, xs, close, fancy_unicode_subscript(subscritp))
             }
             Node::Unit(element, subscritp) => {
                 format!("{}{}", element, fancy_unicode_subscript(&subscritp))
             }
+            Node::Adduct(parts) => {
+                parts
+                    .iter()
+                    .map(|(multiplier, xs)| {
+                        if *multiplier == Number::int(1) {
+                            render_list(xs)
+                        } else {
+                            format!("{}{}", render_number(multiplier), render_list(xs))
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("·")
+            }
+        }
+    }
+    /// The formula unit in plain text, as it would be typed, without the
+    /// coefficient or state (e.g. `Ca(OH)2`, `SO4^2-` or `CuSO4*5H2O`).
+    pub fn formula(&self) -> String {
+        fn subscript(x: &Number) -> String {
+            if *x == Number::int(1) {String::new()} else {render_number(x)}
+        }
+        fn render_list(xs: &[Node]) -> String {
+            xs.iter().map(Node::formula).collect::<Vec<_>>().join("")
+        }
+        match self {
+            Node::Chunk(_, xs, charge, _) => {
+                let xs = if xs.is_empty() {
+                    String::from("e")
+                } else {
+                    render_list(xs)
+                };
+                let sign = if *charge < 0 {"-"} else {"+"};
+                match charge.abs() {
+                    0 => xs,
+                    1 => format!("{}^{}", xs, sign),
+                    x => format!("{}^{}{}", xs, x, sign),
+                }
+            }
+            Node::Parens(xs, sub, Bracket::Round) => {
+                format!("({}){}", render_list(xs), subscript(sub))
+            }
+            Node::Parens(xs, sub, Bracket::Square) => {
+                format!("[{}]{}", render_list(xs), subscript(sub))
+            }
+            Node::Unit(element, sub) => {
+                format!("{}{}", element, subscript(sub))
+            }
+            Node::Adduct(parts) => {
+                parts
+                    .iter()
+                    .map(|(mult, xs)| format!("{}{}", subscript(mult), render_list(xs)))
+                    .collect::<Vec<_>>()
+                    .join("*")
+            }
         }
     }
     pub fn from_str(source: &str) -> Option<Self> {
         crate::chem::parser::parse_group(source).ok().map(|(_, x)| x)
     }
+    /// Like `from_str`, but rejects unknown element symbols.
+    pub fn from_str_strict(source: &str) -> Option<Self> {
+        crate::chem::parser::parse_group_strict(source).ok().map(|(_, x)| x)
+    }
     pub fn root_coefficient(&self) -> Number {
         match self {
-            Node::Chunk(x, _, _) => x.clone(),
-            Node::Parens(_, _) => Number::int(1),
+            Node::Chunk(x, _, _, _) => x.clone(),
+            Node::Parens(_, _, _) => Number::int(1),
             Node::Unit(_, _) => Number::int(1),
+            Node::Adduct(_) => Number::int(1),
+        }
+    }
+    /// Replaces the root coefficient, wrapping the node in a `Chunk` if it
+    /// isn't already one.
+    pub fn with_coefficient(self, coefficient: Coefficient) -> Node {
+        match self {
+            Node::Chunk(_, xs, ch, st) => Node::Chunk(coefficient, xs, ch, st),
+            x => Node::Chunk(coefficient, vec![x], 0, None),
         }
     }
+    /// Replaces the state, wrapping the node in a `Chunk` if it isn't already
+    /// one.
+    pub fn with_state(self, state: Option<State>) -> Node {
+        match self.clone().with_coefficient(self.root_coefficient()) {
+            Node::Chunk(co, xs, ch, _) => Node::Chunk(co, xs, ch, state),
+            x => x,
+        }
+    }
+    /// Charge of a single formula unit (i.e. ignoring the coefficient).
+    pub fn charge(&self) -> Charge {
+        match self {
+            Node::Chunk(_, _, ch, _) => *ch,
+            Node::Parens(_, _, _) => 0,
+            Node::Unit(_, _) => 0,
+            Node::Adduct(_) => 0,
+        }
+    }
+    pub fn state(&self) -> Option<&State> {
+        match self {
+            Node::Chunk(_, _, _, st) => st.as_ref(),
+            _ => None,
+        }
+    }
+    pub fn is_electron(&self) -> bool {
+        match self {
+            Node::Chunk(_, xs, ch, _) => xs.is_empty() && *ch == -1,
+            _ => false,
+        }
+    }
+    /// Atoms in a single formula unit, ignoring the coefficient.
+    pub fn unit_composition(&self) -> Composition {
+        self.clone().with_coefficient(Number::int(1)).composition()
+    }
+    /// Same atoms per formula unit, whatever the charge (e.g. `SO3` and
+    /// `SO3^2-`).
+    pub fn has_same_composition(&self, other: &Node) -> bool {
+        self.unit_composition().hill_order() == other.unit_composition().hill_order()
+    }
+    /// Same formula unit and charge, regardless of coefficient, state or how
+    /// the formula is written (e.g. `CH3COOH` and `C2H4O2`).
+    pub fn is_same_species(&self, other: &Node) -> bool {
+        self.charge() == other.charge() && self.has_same_composition(other)
+    }
+    /// The Hill-system formula of a single formula unit, with the charge as
+    /// in `formula` (e.g. `C2H3O2^-` for acetate).
+    pub fn hill_formula(&self) -> String {
+        self.canonical().formula()
+    }
+    /// A flat chunk of the formula unit in Hill order, keeping the
+    /// coefficient, charge and state. Canonical forms of the same species
+    /// are equal and hash alike, so they work as map keys.
+    pub fn canonical(&self) -> Node {
+        let units = hill_units(&self.unit_composition());
+        let state = self.state().cloned();
+        Node::Chunk(self.root_coefficient(), units, self.charge(), state)
+    }
+    /// A neutral formula unit with these atoms, in Hill order.
+    pub fn from_composition(composition: &Composition) -> Node {
+        Node::Chunk(Number::int(1), hill_units(composition), 0, None)
+    }
     pub fn trans(self, func: Rc<RefCell<dyn Fn(Node) -> Option<Node>>>) -> Option<Node> {
         let new_node = match self {
-            Node::Chunk(co, xs, st) => {
+            Node::Chunk(co, xs, ch, st) => {
                 let xs = xs
                     .into_iter()
                     .filter_map(|x| x.trans(func.clone()))
                     .collect::<Vec<_>>();
-                Node::Chunk(co, xs, st)
+                Node::Chunk(co, xs, ch, st)
             }
-            Node::Parens(xs, sub) => {
+            Node::Parens(xs, sub, bracket) => {
                 let xs = xs
                     .into_iter()
                     .filter_map(|x| x.trans(func.clone()))
                     .collect::<Vec<_>>();
-                Node::Parens(xs, sub)
+                Node::Parens(xs, sub, bracket)
             }
             Node::Unit(x, sub) => {
                 Node::Unit(x, sub)
             }
+            Node::Adduct(parts) => {
+                let parts = parts
+                    .into_iter()
+                    .map(|(mult, xs)| {
+                        let xs = xs
+                            .into_iter()
+                            .filter_map(|x| x.trans(func.clone()))
+                            .collect::<Vec<_>>();
+                        (mult, xs)
+                    })
+                    .collect::<Vec<_>>();
+                Node::Adduct(parts)
+            }
         };
         (func.borrow_mut())(new_node)
     }
-    pub fn atoms(&self) -> Vec<Element> {
-        #[inline]
-        fn go(input: &[Node], mult: Number) -> Vec<Element> {
-            let atms = input
-                .iter()
-                .flat_map(|x| x.atoms())
-                .collect::<Vec<_>>();
-            (1 ..= mult.unpack_integer().unwrap())
-                .into_iter()
-                .flat_map(|_| atms.clone())
-                .collect::<Vec<_>>()
+    /// Number of atoms of each element, including the root coefficient.
+    pub fn composition(&self) -> Composition {
+        fn go(input: &[Node], mult: &Number) -> Composition {
+            let mut total = Composition::default();
+            for x in input {
+                total.add_scaled(&x.composition(), mult);
+            }
+            total
         }
         match self {
-            Node::Chunk(co, xs, _) => {
-                go(xs, co.clone())
+            Node::Chunk(co, xs, _, _) => {
+                go(xs, co)
             }
-            Node::Parens(xs, sub) => {
-                go(xs, sub.clone())
+            Node::Parens(xs, sub, _) => {
+                go(xs, sub)
             }
             Node::Unit(x, sub) => {
-                (1 ..= sub.unpack_integer().unwrap())
-                    .into_iter()
-                    .map(|_| x.clone())
-                    .collect::<Vec<_>>()
+                let mut total = Composition::default();
+                total.add(x, sub);
+                total
             }
-        }
-    }
-    pub fn count(&self, reference: &Element) -> usize {
-        let atoms = self.atoms();
-        let mut counter = 0;
-        for element in atoms {
-            if element == *reference {
-                counter = counter + 1;
+            Node::Adduct(parts) => {
+                let mut total = Composition::default();
+                for (mult, xs) in parts {
+                    total.add_scaled(&go(xs, mult), &Number::int(1));
+                }
+                total
             }
         }
-        counter
+    }
+    /// The distinct elements in this formula, each once and in order; use
+    /// `composition` or `count` for how many atoms of each there are.
+    pub fn atoms(&self) -> Vec<Element> {
+        self.composition().elements()
+    }
+    /// Number of atoms of the given element, including the root coefficient.
+    pub fn count(&self, reference: &Element) -> Number {
+        self.composition().get(reference)
+    }
+    /// Mass of one mole of this substance in g/mol (numerically equal to the
+    /// formula mass in u), ignoring the root coefficient.
+    ///
+    /// Returns `None` if the formula contains an unknown element.
+    pub fn molar_mass(&self) -> Option<Number> {
+        let unit = self.clone().with_coefficient(Number::int(1));
+        unit.composition().0
+            .iter()
+            .map(|(element, count)| Some(count * &element.atomic_weight()?))
+            .sum()
+    }
+    /// Alias of `molar_mass`, for when the answer is wanted in u (daltons).
+    pub fn formula_mass(&self) -> Option<Number> {
+        self.molar_mass()
+    }
+    /// The fraction (between zero and one) of this substance's mass that is
+    /// due to each element.
+    pub fn percent_composition(&self) -> Option<BTreeMap<Element, Number>> {
+        let unit = self.clone().with_coefficient(Number::int(1));
+        let total = unit.molar_mass()?;
+        unit.composition().0
+            .into_iter()
+            .map(|(element, count)| {
+                let mass = count * element.atomic_weight()?;
+                Some((element, mass / total.clone()))
+            })
+            .collect()
     }
     pub fn coefficient_map(
         &self,
         total_elements: &BTreeSet<Element>
-    ) -> BTreeMap<Element, usize> {
+    ) -> BTreeMap<Element, Number> {
+        let composition = self.composition();
         assert_eq!(true, {
-            self.atoms()
-                .into_iter()
-                .all(|atom| total_elements.get(&atom).is_some())
+            composition.0
+                .keys()
+                .all(|atom| total_elements.get(atom).is_some())
         });
         total_elements
             .iter()
             .map(|element| {
-                (element.clone(), self.count(element))
+                (element.clone(), composition.get(element))
             })
             .collect()
     }
@@ -198,8 +504,7 @@ impl Node {
     ) -> Row {
         let map = self.coefficient_map(total_elements);
         let xs = map
-            .values()
-            .map(|x| Number::from_usize(*x))
+            .into_values()
             .collect::<Vec<_>>();
         Row(xs)
     }
@@ -220,11 +525,47 @@ impl Node {
 pub struct Sequence(pub Vec<Node>);
 
 impl Sequence {
+    /// Species joined by `+`, e.g. the reactants alone.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(source: &str) -> Option<Self> {
+        crate::chem::parser::parse_sum(source).ok().map(|(_, xs)| Sequence(xs))
+    }
+    /// The distinct elements across every term, each once and in order.
     pub fn atoms(&self) -> Vec<Element> {
+        self.composition().elements()
+    }
+    /// Total number of atoms of each element, including coefficients.
+    pub fn composition(&self) -> Composition {
+        let mut total = Composition::default();
+        for x in self.0.iter() {
+            total.add_scaled(&x.composition(), &Number::int(1));
+        }
+        total
+    }
+    /// Total number of atoms of the given element, including coefficients.
+    pub fn count(&self, reference: &Element) -> Number {
         self.0
             .iter()
-            .flat_map(|x| x.atoms())
-            .collect::<Vec<_>>()
+            .map(|x| x.count(reference))
+            .sum()
+    }
+    /// Net charge, including coefficients.
+    pub fn charge(&self) -> Number {
+        self.0
+            .iter()
+            .map(|x| &x.root_coefficient() * &Number::from_isize(x.charge()))
+            .sum()
+    }
+    pub fn is_charged(&self) -> bool {
+        self.0.iter().any(|x| x.charge() != 0)
+    }
+    /// Each term with its coefficient reset to one.
+    pub fn formula_units(&self) -> Sequence {
+        let xs = self.0
+            .iter()
+            .map(|x| x.clone().with_coefficient(Number::int(1)))
+            .collect();
+        Sequence(xs)
     }
     pub fn coefficient_column(
         &self,
@@ -248,34 +589,116 @@ impl Sequence {
 pub struct Reaction {
     pub reactants: Sequence,
     pub products: Sequence,
+    pub arrow: Arrow,
+    /// Annotations written over the arrow.
+    pub conditions: Vec<Condition>,
+}
+
+/// The kind of arrow between reactants and products.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrow {
+    /// `->`, `→` or `=`.
+    Forward,
+    /// `<-` or `←`, i.e. the products are written on the left. The
+    /// reactants and products are still where they belong; this only
+    /// changes which side is rendered first.
+    Backward,
+    /// `<=>` or `⇌`.
+    Equilibrium,
+}
+
+impl std::fmt::Display for Arrow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arrow::Forward => write!(f, "⟶"),
+            Arrow::Backward => write!(f, "⟵"),
+            Arrow::Equilibrium => write!(f, "⇌"),
+        }
+    }
+}
+
+/// An annotation over the arrow, e.g. `->[Δ, Pt]`.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    /// `Δ` or `heat`.
+    Heat,
+    /// `hv` or `hν`.
+    Light,
+    Catalyst(Node),
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Heat => write!(f, "Δ"),
+            Condition::Light => write!(f, "hν"),
+            Condition::Catalyst(x) => write!(f, "{}", x.formula()),
+        }
+    }
 }
 
 impl Reaction {
+    /// A one-way reaction without any conditions.
+    pub fn new(reactants: Sequence, products: Sequence) -> Self {
+        Reaction {
+            reactants,
+            products,
+            arrow: Arrow::Forward,
+            conditions: Vec::new(),
+        }
+    }
+    pub fn is_reversible(&self) -> bool {
+        self.arrow == Arrow::Equilibrium
+    }
     pub fn from_str(source: &str) -> Option<Self> {
         crate::chem::parser::parse_reaction(source).ok().map(|(_, x)| x)
     }
+    /// Like `from_str`, but rejects unknown element symbols.
+    pub fn from_str_strict(source: &str) -> Option<Self> {
+        crate::chem::parser::parse_reaction_strict(source).ok().map(|(_, x)| x)
+    }
     pub fn to_string(&self) -> String {
-        let left = self.reactants.0
+        let (left, right) = match self.arrow {
+            Arrow::Backward => (&self.products, &self.reactants),
+            _ => (&self.reactants, &self.products),
+        };
+        let left = left.0
             .iter()
             .map(|x| x.to_string())
             .collect::<Vec<_>>()
             .join(" + ");
-        let right = self.products.0
+        let right = right.0
             .iter()
             .map(|x| x.to_string())
             .collect::<Vec<_>>()
             .join(" + ");
-        format!("{} ⟶   {}", left, right)
+        let conditions = if self.conditions.is_empty() {
+            String::new()
+        } else {
+            let xs = self.conditions
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("[{}]", xs)
+        };
+        format!("{} {}{}   {}", left, self.arrow, conditions, right)
     }
+    /// Every element has the same number of atoms on both sides, and the net
+    /// charge is the same on both sides.
     pub fn is_balanced(&self) -> bool {
-        let left = self.reactants.atoms();
-        let right = self.products.atoms();
-        left.len() == right.len()
+        let atoms_balance = BTreeSet::<Element>::from_iter(self.merge().atoms())
+            .iter()
+            .all(|element| {
+                self.reactants.count(element) == self.products.count(element)
+            });
+        atoms_balance && self.reactants.charge() == self.products.charge()
     }
+    /// Both sides are made up of the same elements.
     pub fn is_valid(&self) -> bool {
         let left = BTreeSet::<Element>::from_iter(self.reactants.atoms());
         let right = BTreeSet::<Element>::from_iter(self.products.atoms());
-        left.len() == right.len()
+        left == right
     }
     pub fn merge(&self) -> Sequence {
         Sequence(vec![
@@ -283,26 +706,211 @@ impl Reaction {
             self.products.0.clone(),
         ].concat())
     }
-    pub fn balance(&self) {
+    /// Returns a copy of this reaction where every coefficient is the smallest
+    /// positive integer that conserves each element (and charge, for ionic
+    /// and half-reactions).
+    ///
+    /// Any preexisting coefficients are ignored.
+    pub fn balance(&self) -> Result<Reaction, BalanceError> {
+        // Formula units only; the existing coefficients are what we solve for.
+        let reactants = self.reactants.formula_units();
+        let products = self.products.formula_units();
         // Must be a BTreeSet data type; we explicitly want an ordered data type.
         let total_elements: BTreeSet<Element> = BTreeSet::from_iter(
-            vec![self.reactants.atoms(), self.products.atoms()].concat()
+            [reactants.atoms(), products.atoms()].concat()
+        );
+        let matrix = Reaction::composition_matrix(
+            &reactants,
+            &products,
+            &total_elements,
         );
+        // Whether dropping the given constraint (row) would permit a nonzero
+        // solution.
+        let rules_out = |row_ix: usize| -> Result<bool, BalanceError> {
+            let rows = (0..matrix.row_len())
+                .filter(|r| *r != row_ix)
+                .map(|r| matrix.unsafe_get_row(r).0.clone())
+                .collect::<Vec<_>>();
+            let without = Matrix::from_rows(rows).ok_or(BalanceError::InvalidMatrix)?;
+            Ok(!without.nullspace_basis().is_empty())
+        };
+        let mut basis = matrix.nullspace_basis();
+        match basis.len() {
+            0 => {
+                let one_sided = total_elements
+                    .iter()
+                    .filter(|element| {
+                        reactants.count(element).is_zero() ||
+                        products.count(element).is_zero()
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                // Otherwise blame the elements whose constraint is what rules
+                // out every nonzero solution.
+                let unconserved = if one_sided.is_empty() {
+                    let mut unconserved = Vec::new();
+                    for (ix, element) in total_elements.iter().enumerate() {
+                        if rules_out(ix)? {
+                            unconserved.push(element.clone());
+                        }
+                    }
+                    unconserved
+                } else {
+                    one_sided
+                };
+                // The charge row (if any) comes after the element rows.
+                let charge = {
+                    matrix.row_len() > total_elements.len() &&
+                    rules_out(total_elements.len())?
+                };
+                Err(BalanceError::NoSolution {unconserved, charge})
+            }
+            1 => {
+                let solution = basis.pop().unwrap();
+                let all_positive = solution.0.iter().all(|x| !x.is_zero()) && {
+                    let sign = solution.0[0].sign();
+                    solution.0.iter().all(|x| x.sign() == sign)
+                };
+                let combination = self.combination(solution);
+                if all_positive {
+                    Ok(combination)
+                } else {
+                    Err(BalanceError::NonPositive {combination})
+                }
+            }
+            _ => {
+                let combinations = basis
+                    .into_iter()
+                    .map(|x| self.combination(x))
+                    .collect();
+                Err(BalanceError::Underdetermined {combinations})
+            }
+        }
+    }
+    /// One row per element, one column per species, where products are
+    /// negated so that `Ax = 0` expresses conservation.
+    ///
+    /// If any species is charged, the last row is the charge of each species.
+    fn composition_matrix(
+        reactants: &Sequence,
+        products: &Sequence,
+        total_elements: &BTreeSet<Element>,
+    ) -> Matrix {
         let mut matrix = Matrix::new();
-        for col in self.reactants.coefficient_column(&total_elements, 1) {
+        for col in reactants.coefficient_column(total_elements, 1) {
             matrix.push_column(col);
         }
-        for col in self.products.coefficient_column(&total_elements, -1) {
+        for col in products.coefficient_column(total_elements, -1) {
             matrix.push_column(col);
         }
-        matrix.push_column(Column(vec![
-            Number::int(0),
-            Number::int(0),
-            Number::int(0),
-        ]));
-        let solved = matrix
-            .non_zero_diagnal()
-            .solve();
+        if reactants.is_charged() || products.is_charged() {
+            let charges = reactants.0
+                .iter()
+                .map(|x| Number::from_isize(x.charge()))
+                .chain(products.0.iter().map(|x| Number::from_isize(-x.charge())))
+                .collect::<Vec<_>>();
+            matrix.push_row(charges);
+        }
+        matrix
+    }
+    /// Converts a solution of the composition matrix into a reaction.
+    ///
+    /// The solution is scaled to the smallest integers, species with a
+    /// negative coefficient are moved to the other side, and species with a
+    /// zero coefficient are dropped.
+    fn combination(&self, solution: Column) -> Reaction {
+        let gcd = solution.0
+            .iter()
+            .fold(Number::int(0), |acc, x| acc.gcd(x));
+        let mut solution = solution.div_each(&gcd);
+        if solution.0.iter().all(|x| x <= &Number::int(0)) {
+            solution = solution.mul_each(&Number::int(-1));
+        }
+        let mut left = Vec::<Node>::new();
+        let mut right = Vec::<Node>::new();
+        let species = self.reactants.0
+            .iter()
+            .map(|x| (x, true))
+            .chain(self.products.0.iter().map(|x| (x, false)));
+        for ((node, is_reactant), co) in species.zip(solution.0) {
+            if co.is_zero() {
+                continue;
+            }
+            let on_left = is_reactant == (co > Number::int(0));
+            let node = node.clone().with_coefficient(co.abs());
+            if on_left {
+                left.push(node);
+            } else {
+                right.push(node);
+            }
+        }
+        Reaction {
+            reactants: Sequence(left),
+            products: Sequence(right),
+            arrow: self.arrow,
+            conditions: self.conditions.clone(),
+        }
+    }
+}
+
+/// Why `Reaction::balance` failed.
+#[derive(Debug, Clone)]
+pub enum BalanceError {
+    /// Only the zero solution conserves every element, i.e. the given species
+    /// cannot react into one another.
+    NoSolution {
+        /// Elements missing from one side; failing that, the elements whose
+        /// conservation constraint alone rules out every nonzero solution.
+        unconserved: Vec<Element>,
+        /// Whether conserving charge alone rules out every nonzero solution.
+        charge: bool,
+    },
+    /// There is more than one independent way to conserve every element
+    /// (e.g. several reactions mixed together), so no unique coefficients.
+    Underdetermined {
+        /// One reaction per basis vector of the solution space.
+        combinations: Vec<Reaction>,
+    },
+    /// The only way to conserve every element gives some species a zero or
+    /// negative coefficient.
+    NonPositive {
+        /// The conserving combination, with species moved to the side they
+        /// would have to be on (and dropped if their coefficient is zero).
+        combination: Reaction,
+    },
+    /// The rows of the composition matrix aren't all the same length, so it
+    /// can't be solved.
+    InvalidMatrix,
+}
+
+impl std::fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BalanceError::NoSolution {unconserved, charge} => {
+                let mut quantities = unconserved
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>();
+                if *charge {
+                    quantities.push(String::from("charge"));
+                }
+                write!(f, "impossible reaction; cannot conserve: {}", quantities.join(", "))
+            }
+            BalanceError::Underdetermined {combinations} => {
+                let combinations = combinations
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                write!(f, "underdetermined reaction; independent combinations: {}", combinations)
+            }
+            BalanceError::NonPositive {combination} => {
+                write!(f, "no positive coefficients; nearest combination: {}", combination.to_string())
+            }
+            BalanceError::InvalidMatrix => {
+                write!(f, "cannot build the composition matrix")
+            }
+        }
     }
 }
 
@@ -369,9 +977,12 @@ pub fn dev() {
     // let source = "C3H8 + O2 -> CO2 + H2O";
     // let source = "Na3PO4 + Ba(NO3)2 -> Ba3(PO4)2 + NaNO3";
     let source = "2XY2 + 4Y -> 8X2 + 8YX";
-    let mut reaction = Reaction::from_str(source).unwrap();
+    let reaction = Reaction::from_str(source).unwrap();
     println!("{}", reaction.to_string());
-    reaction.balance();
+    match reaction.balance() {
+        Ok(balanced) => println!("{}", balanced.to_string()),
+        Err(error) => println!("{}", error),
+    }
 
     // reaction.balance();
     // let elements = BTreeSet::<Element>::from_iter(reaction.merge().atoms());
@@ -396,4 +1007,284 @@ pub fn main() {
     // println!("{}", matrix);
     // println!("{}", constants);
     // println!("{}", x);
-}
\ No newline at end of file
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coefficients(reaction: &Reaction) -> Vec<Number> {
+        reaction
+            .merge()
+            .0
+            .iter()
+            .map(|x| x.root_coefficient())
+            .collect()
+    }
+
+    #[test]
+    fn balance_samples() {
+        let run = |source: &str, expected: &[i32]| {
+            let balanced = Reaction::from_str(source)
+                .unwrap()
+                .balance()
+                .expect("reaction should balance");
+            assert!(balanced.is_balanced());
+            let expected = expected
+                .iter()
+                .map(|x| Number::int(*x))
+                .collect::<Vec<_>>();
+            assert_eq!(coefficients(&balanced), expected, "{}", source);
+        };
+        run("PCl5 + H2O -> H3PO4 + HCl", &[1, 4, 1, 5]);
+        run("Al + O2 -> Al2O3", &[4, 3, 2]);
+        run("C3H8 + O2 -> CO2 + H2O", &[1, 5, 3, 4]);
+        run("Na3PO4 + Ba(NO3)2 -> Ba3(PO4)2 + NaNO3", &[2, 3, 1, 6]);
+        run("Ca(OH)2(aq) + HCl(aq) -> CaCl2(aq) + H2O(l)", &[1, 2, 1, 2]);
+        // Existing coefficients are ignored.
+        run("4H2 + 7O2 -> 3H2O", &[2, 1, 2]);
+        // Charge is conserved too.
+        run("Cu + Ag+ -> Cu^2+ + Ag", &[1, 2, 1, 2]);
+        run("MnO4- + Fe^2+ + H+ -> Mn²⁺ + Fe³⁺ + H2O", &[1, 5, 8, 1, 5, 4]);
+        run("Fe^3+ + e- -> Fe^2+", &[1, 1, 1]);
+    }
+
+    #[test]
+    fn charges() {
+        let run = |source: &str, charge: Charge, rendered: &str| {
+            let node = Node::from_str(source).unwrap();
+            assert_eq!(node.charge(), charge, "{}", source);
+            assert_eq!(node.to_string().trim_end(), rendered);
+        };
+        run("SO4^2-", -2, "1S₁O₄²⁻");
+        run("SO4^-2", -2, "1S₁O₄²⁻");
+        run("SO4²⁻", -2, "1S₁O₄²⁻");
+        run("Fe^3+", 3, "1Fe₁³⁺");
+        run("NH4+", 1, "1N₁H₄⁺");
+        run("Cl-(aq)", -1, "1Cl₁⁻ (aq)");
+        run("2e-", -1, "2e⁻");
+        let formula = |source: &str| Node::from_str(source).unwrap().formula();
+        assert_eq!(formula("2SO4²⁻(aq)"), "SO4^2-");
+        assert_eq!(formula("K4[Fe(CN)6]"), "K4[Fe(CN)6]");
+        assert_eq!(formula("CuSO4·5H2O"), "CuSO4*5H2O");
+        assert_eq!(formula("Na+"), "Na^+");
+        assert!(Node::from_str("e-").unwrap().is_electron());
+        // Too large to be a charge.
+        assert!(Node::from_str("Fe^99999999999999999999+").is_none());
+        assert!(Node::from_str("Fe⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁹⁺").is_none());
+        assert!(Reaction::from_str("Fe^99999999999999999999+ + e- -> Fe").is_none());
+        // Without whitespace, a `+` is a separator rather than a charge.
+        let reaction = Reaction::from_str("H2+O2 -> H2O").unwrap();
+        assert_eq!(reaction.reactants.0.len(), 2);
+    }
+
+    #[test]
+    fn molar_mass() {
+        let water = Node::from_str("H2O").unwrap();
+        assert_eq!(water.molar_mass(), Some(Number::fraction(18015, 1000)));
+        let hydroxide = Node::from_str("2Ca(OH)2").unwrap();
+        assert_eq!(hydroxide.molar_mass(), Some(Number::fraction(74092, 1000)));
+        let glucose = Node::from_str("C6H12O6").unwrap();
+        let carbon = glucose
+            .percent_composition()
+            .unwrap()
+            .remove(&Element::from_str("C"))
+            .unwrap();
+        assert_eq!(carbon, Number::fraction(72066, 180156));
+        assert_eq!(Node::from_str("Xy2").unwrap().molar_mass(), None);
+    }
+
+    #[test]
+    fn nested_groups() {
+        let run = |source: &str, rendered: &str, counts: &[(&str, usize)]| {
+            let node = Node::from_str(source).unwrap();
+            assert_eq!(node.to_string().trim_end(), rendered);
+            for (element, count) in counts {
+                assert_eq!(node.count(&Element::from_str(element)), Number::from_usize(*count), "{}", source);
+            }
+        };
+        run("K4[Fe(CN)6]", "1K₄[Fe₁(C₁N₁)₆]₁", &[("K", 4), ("Fe", 1), ("C", 6), ("N", 6)]);
+        run("((CH3)3C)2O", "1((C₁H₃)₃C₁)₂O₁", &[("C", 8), ("H", 18), ("O", 1)]);
+        run("[Co(NH3)6]Cl3", "1[Co₁(N₁H₃)₆]₁Cl₃", &[("N", 6), ("H", 18), ("Cl", 3)]);
+        // Mismatched brackets aren't a group.
+        let (rest, _) = crate::chem::parser::parse_group("Fe(CN]6").unwrap();
+        assert_eq!(rest, "(CN]6");
+    }
+
+    #[test]
+    fn composition() {
+        let polyethylene = Node::from_str("(C2H4)100000").unwrap();
+        let composition = polyethylene.composition();
+        assert_eq!(composition.get(&Element::from_str("C")), Number::int(200000));
+        assert_eq!(composition.get(&Element::from_str("H")), Number::int(400000));
+        assert_eq!(composition.get(&Element::from_str("O")), Number::int(0));
+        assert_eq!(polyethylene.molar_mass(), Some(Number::int(2805400)));
+        let combustion = Reaction::from_str("(C2H4)100000 + O2 -> CO2 + H2O")
+            .unwrap()
+            .balance()
+            .unwrap();
+        assert!(combustion.is_balanced());
+        assert_eq!(combustion.reactants.0[1].root_coefficient(), Number::int(300000));
+        // One entry per distinct element, not per atom.
+        let elements = |xs: &[&str]| xs.iter().map(|x| Element::from_str(x)).collect::<Vec<_>>();
+        assert_eq!(polyethylene.atoms(), elements(&["C", "H"]));
+        assert_eq!(Node::from_str("2H2O2").unwrap().atoms(), elements(&["H", "O"]));
+        let sequence = Sequence::from_str("CH4 + 2O2").unwrap();
+        assert_eq!(sequence.atoms(), elements(&["C", "H", "O"]));
+    }
+
+    #[test]
+    fn canonical_formulas() {
+        let node = |source: &str| Node::from_str(source).unwrap();
+        let hill = |source: &str| node(source).hill_formula();
+        assert_eq!(hill("CH3COOH"), "C2H4O2");
+        assert_eq!(hill("HOH"), "H2O");
+        assert_eq!(hill("NaCl"), "ClNa");
+        assert_eq!(hill("H2SO4"), "H2O4S");
+        assert_eq!(hill("CuSO4·5H2O"), "CuH10O9S");
+        assert_eq!(hill("2CH3COO-(aq)"), "C2H3O2^-");
+        assert_eq!(hill("e-"), "e^-");
+        // Structural equality goes by how the formula is written.
+        assert_ne!(node("HOH"), node("H2O"));
+        assert_eq!(node("HOH").canonical(), node("H2O").canonical());
+        assert!(node("HOH").is_same_species(&node("H2O")));
+        assert!(node("SO3").has_same_composition(&node("SO3^2-")));
+        assert!(!node("SO3").is_same_species(&node("SO3^2-")));
+        // Coefficient and state are kept.
+        assert_eq!(node("2HOH(l)").canonical(), node("2H2O(l)"));
+        assert_ne!(node("H2O(l)").canonical(), node("H2O(g)").canonical());
+        let species = ["H2O", "HOH", "C2H4O2", "CH3COOH", "OH2"]
+            .iter()
+            .map(|x| node(x).canonical())
+            .collect::<HashSet<_>>();
+        assert_eq!(species.len(), 2);
+    }
+
+    #[test]
+    fn fractional_numbers() {
+        let reaction = Reaction::from_str("H2 + 1/2 O2 -> H2O").unwrap();
+        assert!(reaction.is_balanced());
+        assert_eq!(reaction.reactants.0[1].root_coefficient(), Number::fraction(1, 2));
+        assert_eq!(reaction.to_string(), "1H₂  + 0.5 O₂  ⟶   1H₂O₁ ");
+        let ozone = Node::from_str("2/3 O3").unwrap();
+        assert_eq!(ozone.to_string().trim_end(), "2/3 O₃");
+        assert_eq!(ozone.count(&Element::from_str("O")), Number::int(2));
+        let wustite = Node::from_str("Fe0.95O").unwrap();
+        assert_eq!(wustite.to_string().trim_end(), "1Fe₀.₉₅O₁");
+        assert_eq!(wustite.count(&Element::from_str("Fe")), Number::fraction(19, 20));
+        assert_eq!(wustite.molar_mass(), Some(Number::decimal("69.05175").unwrap()));
+        let balanced = Reaction::from_str("Fe0.95O + O2 -> Fe2O3")
+            .unwrap()
+            .balance()
+            .unwrap();
+        let coefficients = balanced
+            .merge()
+            .0
+            .iter()
+            .map(Node::root_coefficient)
+            .collect::<Vec<_>>();
+        assert_eq!(coefficients, vec![Number::int(80), Number::int(17), Number::int(38)]);
+        let decimal = Node::from_str("1.5O2").unwrap();
+        assert_eq!(decimal.root_coefficient(), Number::fraction(3, 2));
+    }
+
+    #[test]
+    fn arrows() {
+        let run = |source: &str, arrow: Arrow, rendered: &str| {
+            let reaction = Reaction::from_str(source).unwrap();
+            assert_eq!(reaction.arrow, arrow, "{}", source);
+            assert_eq!(reaction.to_string(), rendered);
+            reaction
+        };
+        run("H2 + O2 = H2O", Arrow::Forward, "1H₂  + 1O₂  ⟶   1H₂O₁ ");
+        run("H2 + O2 → H2O", Arrow::Forward, "1H₂  + 1O₂  ⟶   1H₂O₁ ");
+        let backward = run("H2O <- H2 + O2", Arrow::Backward, "1H₂O₁  ⟵   1H₂  + 1O₂ ");
+        assert_eq!(backward.reactants.0.len(), 2);
+        assert_eq!(backward.products.0[0].formula(), "H2O");
+        assert_eq!(backward.balance().unwrap().to_string(), "2H₂O₁  ⟵   2H₂  + 1O₂ ");
+        let haber = run("N2 + 3H2 <=> 2NH3", Arrow::Equilibrium, "1N₂  + 3H₂  ⇌   2N₁H₃ ");
+        assert!(haber.is_reversible());
+        run("N2 + H2 ⇌ NH3", Arrow::Equilibrium, "1N₂  + 1H₂  ⇌   1N₁H₃ ");
+        let reaction = run(
+            "CaCO3 ->[Δ] CaO + CO2",
+            Arrow::Forward,
+            "1Ca₁C₁O₃  ⟶[Δ]   1Ca₁O₁  + 1C₁O₂ ",
+        );
+        assert!(matches!(reaction.conditions[..], [Condition::Heat]));
+        let reaction = run(
+            "SO2 + O2 <=>[V2O5, heat] SO3",
+            Arrow::Equilibrium,
+            "1S₁O₂  + 1O₂  ⇌[V2O5, Δ]   1S₁O₃ ",
+        );
+        // Balancing keeps the arrow and the conditions.
+        let balanced = reaction.balance().unwrap();
+        assert!(balanced.is_reversible());
+        assert_eq!(balanced.conditions.len(), 2);
+        let reaction = run("H2 + Cl2 ->[hv] HCl", Arrow::Forward, "1H₂  + 1Cl₂  ⟶[hν]   1H₁Cl₁ ");
+        assert!(matches!(reaction.conditions[..], [Condition::Light]));
+    }
+
+    #[test]
+    fn hydrates() {
+        for source in &["CuSO4·5H2O", "CuSO4*5H2O", "CuSO4.5H2O"] {
+            let hydrate = Node::from_str(source).unwrap();
+            assert_eq!(hydrate.to_string().trim_end(), "1Cu₁S₁O₄·5H₂O₁");
+            assert_eq!(hydrate.count(&Element::from_str("O")), Number::int(9));
+            assert_eq!(hydrate.count(&Element::from_str("H")), Number::int(10));
+            // Water of crystallization.
+            let water = Node::from_str("5H2O").unwrap();
+            let water = &water.molar_mass().unwrap() * &Number::int(5);
+            let fraction = &water / &hydrate.molar_mass().unwrap();
+            assert_eq!(fraction, Number::fraction(90075, 249677));
+        }
+        let hemihydrate = Node::from_str("CaSO4·0.5H2O").unwrap();
+        assert_eq!(hemihydrate.count(&Element::from_str("H")), Number::int(1));
+        let reaction = Reaction::from_str("CuSO4·5H2O -> CuSO4 + H2O").unwrap();
+        let balanced = reaction.balance().unwrap();
+        assert_eq!(balanced.products.0[1].root_coefficient(), Number::int(5));
+    }
+
+    #[test]
+    fn balance_errors() {
+        let run = |source: &str| {
+            Reaction::from_str(source).unwrap().balance().unwrap_err()
+        };
+        match run("2XY2 + 4Y -> 8X2 + 8YX") {
+            BalanceError::Underdetermined {combinations} => {
+                assert_eq!(combinations.len(), 2);
+                assert!(combinations.iter().all(Reaction::is_balanced));
+            }
+            x => panic!("{:?}", x),
+        }
+        match run("H2 + O2 -> CO2") {
+            BalanceError::NoSolution {unconserved, ..} => {
+                let expected = vec![Element::from_str("C"), Element::from_str("H")];
+                assert_eq!(unconserved, expected);
+            }
+            x => panic!("{:?}", x),
+        }
+        match run("H2 + O2 -> H2O + CO2") {
+            BalanceError::NonPositive {combination} => {
+                assert_eq!(combination.products.0.len(), 1);
+            }
+            x => panic!("{:?}", x),
+        }
+        match run("H2O -> H2 + O2 + H2O2") {
+            BalanceError::Underdetermined {..} => {}
+            x => panic!("{:?}", x),
+        }
+        match run("H2O + H2 -> H2O2") {
+            BalanceError::NonPositive {combination} => {
+                assert!(combination.is_balanced());
+            }
+            x => panic!("{:?}", x),
+        }
+        assert!(!Reaction::from_str("H2 + O2 -> H2O").unwrap().is_balanced());
+    }
+}
@@ -0,0 +1,203 @@
+//! Empirical and molecular formulas from mass percentages or combustion
+//! analysis.
+use crate::numbers::Number;
+use crate::chem::data::*;
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormulaError {
+    /// No element has a positive mass.
+    Empty,
+    UnknownElement(Element),
+    /// No multiplier up to `MAX_MULTIPLIER` brings the mole ratios within
+    /// `TOLERANCE` of whole numbers.
+    NoWholeRatio,
+    /// The molar mass isn't a whole multiple of the empirical formula's.
+    NotAMultiple,
+    /// The carbon and hydrogen from combustion outweigh the sample.
+    InconsistentMasses,
+}
+
+impl std::fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormulaError::Empty => {
+                write!(f, "no element masses given")
+            }
+            FormulaError::UnknownElement(x) => {
+                write!(f, "unknown element {}", x)
+            }
+            FormulaError::NoWholeRatio => {
+                write!(f, "the mole ratios are not close to whole numbers")
+            }
+            FormulaError::NotAMultiple => {
+                write!(f, "the molar mass is not a multiple of the empirical formula mass")
+            }
+            FormulaError::InconsistentMasses => {
+                write!(f, "the combustion products contain more mass than the sample")
+            }
+        }
+    }
+}
+
+/// How far (in atoms) a scaled mole ratio may be from a whole number, and
+/// likewise how far the molar mass may be from a whole multiple of the
+/// empirical formula mass. Measured percentages are rarely exact, so e.g.
+/// a ratio of 1.96 is read as 2.
+pub fn tolerance() -> Number {
+    Number::fraction(1, 10)
+}
+
+/// Ratios such as 1.33 or 1.25 need every count multiplied (by 3 and 4);
+/// multipliers are tried from one up to this.
+pub const MAX_MULTIPLIER: i32 = 10;
+
+fn is_whole(x: &Number) -> bool {
+    (x - &x.round()).abs() <= tolerance()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// FORMULAS
+///////////////////////////////////////////////////////////////////////////////
+
+/// The empirical formula from the mass of each element in a sample. Mass
+/// percentages work the same way, as the masses in a 100 g sample.
+pub fn empirical_formula(masses: &[(Element, Number)]) -> Result<Node, FormulaError> {
+    let moles = masses
+        .iter()
+        .filter(|(_, mass)| *mass > Number::int(0))
+        .map(|(element, mass)| {
+            let weight = element
+                .atomic_weight()
+                .ok_or_else(|| FormulaError::UnknownElement(element.clone()))?;
+            Ok((element.clone(), mass / &weight))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let smallest = moles
+        .iter()
+        .map(|(_, x)| x.clone())
+        .min()
+        .ok_or(FormulaError::Empty)?;
+    let ratios = moles
+        .into_iter()
+        .map(|(element, x)| (element, &x / &smallest))
+        .collect::<Vec<_>>();
+    let multiplier = (1..=MAX_MULTIPLIER)
+        .map(Number::int)
+        .find(|m| ratios.iter().all(|(_, x)| is_whole(&(x * m))))
+        .ok_or(FormulaError::NoWholeRatio)?;
+    let mut composition = Composition::default();
+    for (element, x) in ratios.iter() {
+        composition.add(element, &(x * &multiplier).round());
+    }
+    Ok(Node::from_composition(&composition))
+}
+
+/// The empirical formula of a compound of carbon, hydrogen and (by
+/// difference) oxygen, from the masses of CO₂ and H₂O its combustion gives.
+/// All masses are in the same unit. Oxygen is left out if its mass by
+/// difference is within 1% of the sample mass of zero.
+pub fn combustion_analysis(
+    sample: &Number,
+    carbon_dioxide: &Number,
+    water: &Number,
+) -> Result<Node, FormulaError> {
+    let mass_of = |formula: &str, element: &str, product: &Number| {
+        let node = Node::from_str(formula).unwrap();
+        let element = Element::from_str(element);
+        let fraction = node.percent_composition().unwrap().remove(&element).unwrap();
+        (element, &fraction * product)
+    };
+    let carbon = mass_of("CO2", "C", carbon_dioxide);
+    let hydrogen = mass_of("H2O", "H", water);
+    let oxygen = &(sample - &carbon.1) - &hydrogen.1;
+    let negligible = sample * &Number::fraction(1, 100);
+    if oxygen < -negligible.clone() {
+        return Err(FormulaError::InconsistentMasses)
+    }
+    let mut masses = vec![carbon, hydrogen];
+    if oxygen > negligible {
+        masses.push((Element::from_str("O"), oxygen));
+    }
+    empirical_formula(&masses)
+}
+
+/// The molecular formula with the given molar mass (in g/mol), which must
+/// be a whole multiple of the empirical formula mass.
+pub fn molecular_formula(empirical: &Node, molar_mass: &Number) -> Result<Node, FormulaError> {
+    let unit = empirical.unit_composition();
+    let unknown = unit
+        .elements()
+        .into_iter()
+        .find(|x| !x.is_known());
+    if let Some(element) = unknown {
+        return Err(FormulaError::UnknownElement(element))
+    }
+    let formula_mass = empirical.molar_mass().ok_or(FormulaError::Empty)?;
+    let ratio = molar_mass
+        .save_div(&formula_mass)
+        .ok_or(FormulaError::Empty)?;
+    let multiple = ratio.round();
+    if multiple < Number::int(1) || !is_whole(&ratio) {
+        return Err(FormulaError::NotAMultiple)
+    }
+    let mut composition = Composition::default();
+    composition.add_scaled(&unit, &multiple);
+    Ok(Node::from_composition(&composition))
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn percentages(xs: &[(&str, &str)]) -> Vec<(Element, Number)> {
+        xs.iter()
+            .map(|(element, x)| (Element::from_str(element), Number::decimal(x).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn from_mass_percentages() {
+        let glucose = percentages(&[("C", "40.0"), ("H", "6.7"), ("O", "53.3")]);
+        let empirical = empirical_formula(&glucose).unwrap();
+        assert_eq!(empirical.formula(), "CH2O");
+        let molecular = molecular_formula(&empirical, &Number::decimal("180.16").unwrap()).unwrap();
+        assert_eq!(molecular.formula(), "C6H12O6");
+        // A ratio of 1.5 needs doubling.
+        let rust = percentages(&[("Fe", "69.94"), ("O", "30.06")]);
+        assert_eq!(empirical_formula(&rust).unwrap().formula(), "Fe2O3");
+        // And 1.33 needs tripling.
+        let magnetite = percentages(&[("Fe", "72.36"), ("O", "27.64")]);
+        assert_eq!(empirical_formula(&magnetite).unwrap().formula(), "Fe3O4");
+        let error = molecular_formula(&empirical, &Number::int(45));
+        assert_eq!(error, Err(FormulaError::NotAMultiple));
+        let unknown = percentages(&[("Xy", "50"), ("O", "50")]);
+        assert_eq!(
+            empirical_formula(&unknown),
+            Err(FormulaError::UnknownElement(Element::from_str("Xy")))
+        );
+        assert_eq!(empirical_formula(&[]), Err(FormulaError::Empty));
+    }
+
+    #[test]
+    fn from_combustion() {
+        let number = |x: &str| Number::decimal(x).unwrap();
+        // One millimole of ethanol gives two of CO₂ and three of H₂O.
+        let ethanol = combustion_analysis(&number("46.069"), &number("88.018"), &number("54.045"));
+        assert_eq!(ethanol.unwrap().formula(), "C2H6O");
+        // Measured masses, and no oxygen in the sample.
+        let hexane = combustion_analysis(&number("0.2154"), &number("0.6601"), &number("0.3154"));
+        assert_eq!(hexane.unwrap().formula(), "C3H7");
+        let error = combustion_analysis(&number("0.1"), &number("0.6601"), &number("0.3154"));
+        assert_eq!(error, Err(FormulaError::InconsistentMasses));
+    }
+}
@@ -0,0 +1,399 @@
+//! Equilibrium constant expressions.
+use num::BigRational;
+
+use crate::ast::expr::{Expr, Symbol};
+use crate::numbers::Number;
+use crate::chem::data::*;
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EquilibriumError {
+    /// Only reactions written with `<=>` or `⇌` have an equilibrium constant.
+    NotReversible,
+    /// Kp needs at least one species in the gas phase.
+    NoGases,
+    /// The expression couldn't be solved for the unknown, e.g. because another
+    /// symbol was left without a value.
+    Unsolvable,
+    /// An initial concentration was given for a species that isn't in the
+    /// reaction.
+    UnknownSpecies(String),
+}
+
+impl std::fmt::Display for EquilibriumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EquilibriumError::NotReversible => {
+                write!(f, "not an equilibrium; use <=> or ⇌")
+            }
+            EquilibriumError::NoGases => {
+                write!(f, "no species are in the gas phase")
+            }
+            EquilibriumError::Unsolvable => {
+                write!(f, "cannot solve for the unknown")
+            }
+            EquilibriumError::UnknownSpecies(x) => {
+                write!(f, "{} is not in the reaction", x)
+            }
+        }
+    }
+}
+
+/// An ICE (initial, change, equilibrium) table, solved for the extent of
+/// reaction x.
+#[derive(Debug, Clone)]
+pub struct IceTable {
+    /// The species in the equilibrium expression, reactants first.
+    pub species: Vec<Node>,
+    pub initial: Vec<Number>,
+    /// The change in each concentration per unit of x; i.e. the coefficient,
+    /// negated for reactants.
+    pub change: Vec<Number>,
+    /// `K·Π[reactants] − Π[products]` as a polynomial in x, lowest power
+    /// first.
+    pub polynomial: Vec<Number>,
+    pub extent: Number,
+    pub equilibrium: Vec<Number>,
+}
+
+impl IceTable {
+    /// Whether treating x as negligible would have been justified: no species
+    /// that started out present loses more than 5% of its initial
+    /// concentration.
+    pub fn approximation_is_valid(&self) -> bool {
+        let limit = Number::fraction(1, 20);
+        self.initial
+            .iter()
+            .zip(self.equilibrium.iter())
+            .filter(|(start, _)| !start.is_zero())
+            .all(|(start, end)| start - end <= &limit * start)
+    }
+    pub fn concentration(&self, node: &Node) -> Option<&Number> {
+        self.species
+            .iter()
+            .position(|x| x.is_same_species(node))
+            .map(|ix| &self.equilibrium[ix])
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// EQUILIBRIUM CONSTANTS
+///////////////////////////////////////////////////////////////////////////////
+
+/// The symbol for the molar concentration of a species, e.g. `[NH3]`.
+pub fn concentration_symbol(node: &Node) -> String {
+    format!("[{}]", node.formula())
+}
+
+/// The symbol for the partial pressure of a species, e.g. `P(NH3)`.
+pub fn pressure_symbol(node: &Node) -> String {
+    format!("P({})", node.formula())
+}
+
+impl Reaction {
+    /// The concentration equilibrium constant, Kc, as products over reactants
+    /// with each coefficient as an exponent. Pure solids and liquids are left
+    /// out.
+    pub fn kc(&self) -> Result<Expr, EquilibriumError> {
+        let include = |x: &Node| !matches!(x.state(), Some(State::S) | Some(State::L));
+        self.equilibrium_expression(include, concentration_symbol)
+    }
+    /// The pressure equilibrium constant, Kp, over the gaseous species.
+    pub fn kp(&self) -> Result<Expr, EquilibriumError> {
+        let include = |x: &Node| matches!(x.state(), Some(State::G));
+        let has_gases = self.merge().0.iter().any(include);
+        if !has_gases && self.is_reversible() {
+            return Err(EquilibriumError::NoGases)
+        }
+        self.equilibrium_expression(include, pressure_symbol)
+    }
+    fn equilibrium_expression(
+        &self,
+        include: impl Fn(&Node) -> bool,
+        symbol: fn(&Node) -> String,
+    ) -> Result<Expr, EquilibriumError> {
+        if !self.is_reversible() {
+            return Err(EquilibriumError::NotReversible)
+        }
+        let factors = |xs: &Sequence| -> Expr {
+            let xs = xs.0
+                .iter()
+                .filter(|x| include(x) && !x.is_electron())
+                .map(|x| {
+                    let sym = Expr::Sym(Symbol::Var(symbol(x)));
+                    let co = x.root_coefficient();
+                    if co == Number::int(1) {sym} else {sym.pow(co.into())}
+                })
+                .collect::<Vec<_>>();
+            if xs.is_empty() {
+                Expr::multiplicative_identity()
+            } else {
+                Expr::Product(xs)
+            }
+        };
+        Ok(Expr::ratio(factors(&self.products), factors(&self.reactants)))
+    }
+}
+
+/// Solves `expression = k` for the one symbol that isn't given a value.
+pub fn solve_equilibrium(
+    expression: &Expr,
+    k: &Number,
+    known: &[(&str, Number)],
+    unknown: &str,
+) -> Result<Number, EquilibriumError> {
+    let expression = known
+        .iter()
+        .fold(expression.clone(), |expr, (name, value)| {
+            let value = Expr::Num(BigRational::from(value.clone()));
+            expr.substitute(name, &value)
+        });
+    expression
+        .solve_for(unknown, &k.clone().into())
+        .map(Number::from)
+        .ok_or(EquilibriumError::Unsolvable)
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// ICE TABLES
+///////////////////////////////////////////////////////////////////////////////
+
+impl Reaction {
+    /// Builds the ICE table from the coefficients and solves `K = Kc` for the
+    /// extent x. Species without an initial concentration start at zero, and
+    /// pure solids and liquids are left out (as in `kc`).
+    ///
+    /// Every concentration stays non-negative for x in some interval, over
+    /// which `K·Π[reactants] − Π[products]` falls from positive to negative;
+    /// so there is exactly one physically meaningful root, which we find by
+    /// bisection.
+    pub fn ice_table(
+        &self,
+        k: &Number,
+        initial: &[(Node, Number)],
+    ) -> Result<IceTable, EquilibriumError> {
+        if !self.is_reversible() {
+            return Err(EquilibriumError::NotReversible)
+        }
+        let zero = Number::int(0);
+        if *k <= zero || initial.iter().any(|(_, c)| *c < zero) {
+            return Err(EquilibriumError::Unsolvable)
+        }
+        let all = self.merge();
+        for (node, _) in initial.iter() {
+            if !all.0.iter().any(|x| x.is_same_species(node)) {
+                return Err(EquilibriumError::UnknownSpecies(node.formula()))
+            }
+        }
+        let include = |x: &&Node| {
+            !matches!(x.state(), Some(State::S) | Some(State::L)) && !x.is_electron()
+        };
+        let mut species = Vec::new();
+        let mut change = Vec::new();
+        for x in self.reactants.0.iter().filter(include) {
+            species.push(x.clone());
+            change.push(-x.root_coefficient());
+        }
+        for x in self.products.0.iter().filter(include) {
+            species.push(x.clone());
+            change.push(x.root_coefficient());
+        }
+        let start = species
+            .iter()
+            .map(|x| {
+                initial
+                    .iter()
+                    .find(|(y, _)| x.is_same_species(y))
+                    .map(|(_, c)| c.clone())
+                    .unwrap_or_else(|| zero.clone())
+            })
+            .collect::<Vec<_>>();
+        // Each concentration is `c₀ + νx`, raised to the power |ν|.
+        let mut reactant_side = vec![k.clone()];
+        let mut product_side = vec![Number::int(1)];
+        for (c, nu) in start.iter().zip(change.iter()) {
+            let power = nu.abs().unpack_integer().ok_or(EquilibriumError::Unsolvable)?;
+            let side = if *nu < zero {&mut reactant_side} else {&mut product_side};
+            for _ in 0..power {
+                *side = poly_mul(side, &[c.clone(), nu.clone()]);
+            }
+        }
+        let polynomial = poly_sub(&reactant_side, &product_side);
+        if polynomial.len() < 2 {
+            return Err(EquilibriumError::Unsolvable)
+        }
+        let f = |x: &Number| poly_eval(&polynomial, x);
+        // Bounds that keep every concentration non-negative.
+        let bound = |reactants: bool| {
+            start
+                .iter()
+                .zip(change.iter())
+                .filter(|(_, nu)| (**nu < zero) == reactants)
+                .map(|(c, nu)| -(c / nu))
+                .reduce(|l, r| if reactants {l.min(r)} else {l.max(r)})
+        };
+        let mut upper = match bound(true) {
+            Some(x) => x,
+            None => {
+                let mut x = Number::int(1);
+                while f(&x) > zero {
+                    x = &x * &Number::int(2);
+                }
+                x
+            }
+        };
+        let mut lower = match bound(false) {
+            Some(x) => x,
+            None => {
+                let mut x = Number::int(-1);
+                while f(&x) < zero {
+                    x = &x * &Number::int(2);
+                }
+                x
+            }
+        };
+        let midpoint = |lower: &Number, upper: &Number| &(lower + upper) / &Number::int(2);
+        // A single species (e.g. only a gas over solids) is solved exactly.
+        let mut extent = match &polynomial[..] {
+            [c0, c1] => -(c0 / c1),
+            _ => midpoint(&lower, &upper),
+        };
+        let tolerance = Number::fraction(1, 1u64 << 60);
+        while !f(&extent).is_zero() && &upper - &lower > tolerance {
+            if f(&extent) > zero {
+                lower = extent;
+            } else {
+                upper = extent;
+            }
+            extent = midpoint(&lower, &upper);
+        }
+        let equilibrium = start
+            .iter()
+            .zip(change.iter())
+            .map(|(c, nu)| c + &(nu * &extent))
+            .collect();
+        Ok(IceTable {
+            species,
+            initial: start,
+            change,
+            polynomial,
+            extent,
+            equilibrium,
+        })
+    }
+}
+
+fn poly_mul(left: &[Number], right: &[Number]) -> Vec<Number> {
+    let mut result = vec![Number::int(0); left.len() + right.len() - 1];
+    for (i, x) in left.iter().enumerate() {
+        for (j, y) in right.iter().enumerate() {
+            result[i + j] = &result[i + j] + &(x * y);
+        }
+    }
+    result
+}
+
+fn poly_sub(left: &[Number], right: &[Number]) -> Vec<Number> {
+    let zero = Number::int(0);
+    let mut result = (0..std::cmp::max(left.len(), right.len()))
+        .map(|ix| {
+            left.get(ix).unwrap_or(&zero) - right.get(ix).unwrap_or(&zero)
+        })
+        .collect::<Vec<_>>();
+    while result.last().is_some_and(Number::is_zero) {
+        result.pop();
+    }
+    result
+}
+
+fn poly_eval(polynomial: &[Number], x: &Number) -> Number {
+    polynomial
+        .iter()
+        .rev()
+        .fold(Number::int(0), |acc, c| &(&acc * x) + c)
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equilibrium_constants() {
+        let haber = Reaction::from_str("N2(g) + 3H2(g) <=> 2NH3(g)").unwrap();
+        assert_eq!(haber.kc().unwrap().to_string(), "[NH3]² * 1/([N2] * [H2]³)");
+        assert_eq!(haber.kp().unwrap().to_string(), "P(NH3)² * 1/(P(N2) * P(H2)³)");
+        let limestone = Reaction::from_str("CaCO3(s) <=> CaO(s) + CO2(g)").unwrap();
+        assert_eq!(limestone.kc().unwrap().eval().to_string(), "[CO2]");
+        assert_eq!(limestone.kp().unwrap().eval().to_string(), "P(CO2)");
+        let one_way = Reaction::from_str("N2 + 3H2 -> 2NH3").unwrap();
+        assert_eq!(one_way.kc().unwrap_err(), EquilibriumError::NotReversible);
+        let aqueous = Reaction::from_str("AgCl(s) <=> Ag+(aq) + Cl-(aq)").unwrap();
+        assert_eq!(aqueous.kp().unwrap_err(), EquilibriumError::NoGases);
+        assert_eq!(aqueous.kc().unwrap().eval().to_string(), "[Ag^+] * [Cl^-]");
+    }
+
+    #[test]
+    fn solve() {
+        let haber = Reaction::from_str("N2(g) + 3H2(g) <=> 2NH3(g)").unwrap();
+        let kc = haber.kc().unwrap();
+        let known = [
+            ("[N2]", Number::fraction(1, 10)),
+            ("[H2]", Number::fraction(2, 10)),
+        ];
+        let ammonia = solve_equilibrium(&kc, &Number::fraction(1, 2), &known, "[NH3]").unwrap();
+        assert_eq!(ammonia, Number::fraction(1, 50));
+        let known = [
+            ("[N2]", Number::fraction(1, 10)),
+            ("[NH3]", Number::fraction(1, 50)),
+        ];
+        let hydrogen = solve_equilibrium(&kc, &Number::fraction(1, 2), &known, "[H2]").unwrap();
+        assert_eq!(hydrogen, Number::fraction(1, 5));
+        let error = solve_equilibrium(&kc, &Number::int(1), &known[..1], "[H2]").unwrap_err();
+        assert_eq!(error, EquilibriumError::Unsolvable);
+    }
+
+    #[test]
+    fn ice_tables() {
+        let close = |x: &Number, y: Number| {
+            assert!((x - &y).abs() < Number::fraction(1, 100_000_000), "{:?} ≠ {:?}", x, y);
+        };
+        let node = |x: &str| Node::from_str(x).unwrap();
+        // Weak acid: x² / (0.1 − x) = 1.8e-5.
+        let acetic = Reaction::from_str("CH3COOH(aq) <=> H+(aq) + CH3COO-(aq)").unwrap();
+        let k = Number::fraction(18, 1_000_000);
+        let table = acetic.ice_table(&k, &[(node("CH3COOH"), Number::fraction(1, 10))]).unwrap();
+        assert_eq!(table.change, vec![Number::int(-1), Number::int(1), Number::int(1)]);
+        assert_eq!(table.polynomial.len(), 3);
+        close(&table.extent, Number::double(0.00133267));
+        close(table.concentration(&node("CH3COOH")).unwrap(), Number::double(0.09866733));
+        assert!(table.approximation_is_valid());
+        // Too dilute for x to be negligible.
+        let dilute = acetic.ice_table(&k, &[(node("CH3COOH"), Number::fraction(1, 10_000))]).unwrap();
+        assert!(!dilute.approximation_is_valid());
+        // Exact root: 4x² / ((1 − x)(1 − x)) = 4 at x = 1/2.
+        let hi = Reaction::from_str("H2(g) + I2(g) <=> 2HI(g)").unwrap();
+        let start = [(node("H2"), Number::int(1)), (node("I2"), Number::int(1))];
+        let table = hi.ice_table(&Number::int(4), &start).unwrap();
+        assert_eq!(table.extent, Number::fraction(1, 2));
+        assert_eq!(table.equilibrium, vec![Number::fraction(1, 2), Number::fraction(1, 2), Number::int(1)]);
+        // Runs backwards when only products are present.
+        let table = hi.ice_table(&Number::int(4), &[(node("HI"), Number::int(2))]).unwrap();
+        assert_eq!(table.extent, Number::fraction(-1, 2));
+        // Solids are left out, and the gas alone is bounded only by K.
+        let limestone = Reaction::from_str("CaCO3(s) <=> CaO(s) + CO2(g)").unwrap();
+        let table = limestone.ice_table(&Number::fraction(3, 1000), &[]).unwrap();
+        assert_eq!(table.equilibrium, vec![Number::fraction(3, 1000)]);
+        let error = hi.ice_table(&Number::int(4), &[(node("NH3"), Number::int(1))]).unwrap_err();
+        assert_eq!(error, EquilibriumError::UnknownSpecies(String::from("NH3")));
+    }
+}
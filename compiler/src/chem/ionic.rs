@@ -0,0 +1,384 @@
+//! Complete and net ionic equations for reactions in aqueous solution.
+use crate::numbers::Number;
+use crate::chem::data::*;
+use crate::chem::periodic::ElementInfo;
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct IonicEquations {
+    /// The balanced molecular equation, with predicted states (e.g. `(s)` for
+    /// precipitates).
+    pub molecular: Reaction,
+    /// Every strong electrolyte split into its ions.
+    pub complete: Reaction,
+    /// The complete ionic equation without the spectator ions.
+    pub net: Reaction,
+    /// Ions found unchanged on both sides of the complete ionic equation.
+    pub spectators: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+pub enum IonicError {
+    Unbalanced(BalanceError),
+    /// Every species cancels; e.g. mixing solutions of NaCl and KNO₃.
+    NoReaction,
+}
+
+impl std::fmt::Display for IonicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IonicError::Unbalanced(error) => {
+                write!(f, "cannot balance the molecular equation: {}", error)
+            }
+            IonicError::NoReaction => {
+                write!(f, "no reaction; every ion is a spectator")
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// TABLES
+///////////////////////////////////////////////////////////////////////////////
+
+const POLYATOMIC_IONS: &[&str] = &[
+    "NH4^+",
+    "OH^-", "CN^-", "SCN^-", "CH3COO^-", "MnO4^-",
+    "NO3^-", "NO2^-", "ClO4^-", "ClO3^-", "ClO2^-", "ClO^-",
+    "HCO3^-", "HSO4^-", "H2PO4^-",
+    "CO3^2-", "SO4^2-", "SO3^2-", "S2O3^2-", "HPO4^2-",
+    "CrO4^2-", "Cr2O7^2-", "C2O4^2-",
+    "PO4^3-",
+];
+
+const MONATOMIC_ANIONS: &[(&str, Charge)] = &[
+    ("F", -1), ("Cl", -1), ("Br", -1), ("I", -1),
+    ("O", -2), ("S", -2),
+    ("N", -3), ("P", -3),
+];
+
+const STRONG_ACIDS: &[&str] = &["HCl", "HBr", "HI", "HNO3", "H2SO4", "HClO4", "HClO3"];
+
+/// Solubility rules for an ionic compound, as formulas of the ions. The
+/// first rule that applies wins, and anything not covered (carbonates,
+/// phosphates, sulfides, oxides, …) is insoluble.
+///
+/// Mercury(I) is `Hg^+` here, since we split formula units by element.
+pub(crate) fn is_soluble(cation: &Node, anion: &Node) -> bool {
+    let is_group_1 = match cation.composition().elements().as_slice() {
+        [x] => x.info().is_some_and(|x| x.group == Some(1) && x.symbol != "H"),
+        _ => false,
+    };
+    let cation = cation.formula();
+    if is_group_1 || cation == "NH4^+" {
+        return true
+    }
+    match anion.formula().as_str() {
+        "NO3^-" | "CH3COO^-" | "ClO4^-" | "ClO3^-" => true,
+        "Cl^-" | "Br^-" | "I^-" => {
+            !matches!(cation.as_str(), "Ag^+" | "Pb^2+" | "Hg^+" | "Cu^+")
+        }
+        "SO4^2-" => {
+            !matches!(cation.as_str(), "Ca^2+" | "Sr^2+" | "Ba^2+" | "Pb^2+" | "Ag^+" | "Hg^+")
+        }
+        "OH^-" => matches!(cation.as_str(), "Ca^2+" | "Sr^2+" | "Ba^2+"),
+        _ => false,
+    }
+}
+
+fn is_metal(element: &Element) -> bool {
+    element.info().is_some_and(ElementInfo::is_metal)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// DISSOCIATION
+///////////////////////////////////////////////////////////////////////////////
+
+fn ion(xs: Vec<Node>, count: Number, charge: Charge) -> Node {
+    Node::Chunk(count, xs, charge, Some(State::Aq))
+}
+
+/// The atoms and charge of the polyatomic ion with the same composition.
+fn polyatomic_ion(xs: &[Node]) -> Option<(Vec<Node>, Charge)> {
+    let group = Node::Chunk(Number::int(1), xs.to_vec(), 0, None);
+    POLYATOMIC_IONS
+        .iter()
+        .map(|x| Node::from_str(x).unwrap())
+        .find(|x| x.composition() == group.composition())
+        .and_then(|x| match x {
+            Node::Chunk(_, ys, charge, _) => Some((ys, charge)),
+            _ => None,
+        })
+}
+
+/// Splits one formula unit of a (neutral) ionic compound or acid into its
+/// cation and anion, with the number of each as the coefficient; e.g.
+/// `Ca(NO3)2` into `Ca²⁺` and `2NO₃⁻`.
+///
+/// The cation comes first and is a metal, hydrogen or ammonium. The anion is
+/// either a bracketed group, a monatomic anion or everything that's left.
+/// Carboxylic acids (e.g. `CH3COOH`) give up the final hydrogen.
+pub(crate) fn dissociate(node: &Node) -> Option<(Node, Node)> {
+    let xs = match node {
+        Node::Chunk(_, xs, 0, _) => xs,
+        _ => return None,
+    };
+    let hydrogen = Element::from_str("H");
+    if node.formula().ends_with("COOH") {
+        let anion = xs[..xs.len() - 1].to_vec();
+        let cation = vec![Node::Unit(hydrogen, Number::int(1))];
+        return Some((ion(cation, Number::int(1), 1), ion(anion, Number::int(1), -1)))
+    }
+    let unit = |x: &Element| vec![Node::Unit(x.clone(), Number::int(1))];
+    let ammonium = |xs: &[Node]| {
+        polyatomic_ion(xs).filter(|(_, charge)| *charge > 0)
+    };
+    // The cation's charge is only known up front for ammonium.
+    let (cation, known_charge, count, rest) = match &xs[..] {
+        [Node::Parens(ys, n, _), rest @ ..] if ammonium(ys).is_some() => {
+            (ys.clone(), Some(1), n.clone(), rest)
+        }
+        [a, b, rest @ ..] if ammonium(&[a.clone(), b.clone()]).is_some() => {
+            (vec![a.clone(), b.clone()], Some(1), Number::int(1), rest)
+        }
+        [Node::Unit(x, n), rest @ ..] if is_metal(x) || *x == hydrogen => {
+            (unit(x), None, n.clone(), rest)
+        }
+        _ => return None,
+    };
+    let monatomic = |x: &Element| {
+        MONATOMIC_ANIONS
+            .iter()
+            .find(|(symbol, _)| *symbol == x.0)
+            .map(|(_, charge)| (unit(x), *charge))
+    };
+    let ((anion, anion_charge), anion_count) = match rest {
+        [] => return None,
+        [Node::Parens(ys, m, _)] => (polyatomic_ion(ys)?, m.clone()),
+        [Node::Unit(x, m)] if monatomic(x).is_some() => (monatomic(x)?, m.clone()),
+        ys => (polyatomic_ion(ys)?, Number::int(1)),
+    };
+    let total = &Number::from_isize(-anion_charge) * &anion_count;
+    let charge = (&total / &count).unpack_integer().filter(|x| *x > 0)?;
+    if known_charge.is_some_and(|x| x != charge) {
+        return None
+    }
+    Some((ion(cation, count, charge), ion(anion, anion_count, anion_charge)))
+}
+
+/// The neutral compound of the two ions, written the usual way; e.g. `Ca²⁺`
+/// and `NO₃⁻` make `Ca(NO3)2`, and `H⁺` and `OH⁻` make `H2O`.
+pub(crate) fn compound(cation: &Node, anion: &Node) -> Node {
+    let positive = cation.charge();
+    let negative = -anion.charge();
+    let common = num::integer::gcd(positive, negative);
+    let part = |x: &Node, count: isize| -> Vec<Node> {
+        let xs = match x {
+            Node::Chunk(_, xs, _, _) => xs.clone(),
+            x => vec![x.clone()],
+        };
+        let count = Number::from_isize(count);
+        match &xs[..] {
+            _ if count == Number::int(1) => xs,
+            [Node::Unit(element, n)] => vec![Node::Unit(element.clone(), n * &count)],
+            _ => vec![Node::Parens(xs, count, Bracket::Round)],
+        }
+    };
+    let xs = [
+        part(cation, negative / common),
+        part(anion, positive / common),
+    ].concat();
+    let node = Node::Chunk(Number::int(1), xs, 0, None);
+    let water = Node::from_str("H2O").unwrap();
+    if node.is_same_species(&water) {water} else {node}
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// IONIC EQUATIONS
+///////////////////////////////////////////////////////////////////////////////
+
+impl Reaction {
+    /// Derives the complete and net ionic equations, balancing the molecular
+    /// equation first if needed.
+    ///
+    /// Strong acids and soluble ionic compounds are split into ions. Ionic
+    /// compounds without a state are given one by the solubility rules, so
+    /// precipitates come out as `(s)`; explicit `(s)`, `(l)` and `(g)` states
+    /// are kept. Everything else (water, weak acids, gases, …) stays
+    /// molecular.
+    pub fn ionic_equations(&self) -> Result<IonicEquations, IonicError> {
+        let balanced = if self.is_balanced() {
+            self.clone()
+        } else {
+            self.balance().map_err(IonicError::Unbalanced)?
+        };
+        let strong_acids = STRONG_ACIDS
+            .iter()
+            .map(|x| Node::from_str(x).unwrap())
+            .collect::<Vec<_>>();
+        // Each species with its predicted state, and its ions if it's a strong
+        // electrolyte.
+        let split = |xs: &Sequence| -> (Vec<Node>, Vec<Node>) {
+            let mut molecular = Vec::new();
+            let mut ionic = Sequence(Vec::new());
+            for x in xs.0.iter() {
+                let in_solution = matches!(x.state(), None | Some(State::Aq));
+                let ions = dissociate(x).filter(|_| in_solution);
+                let node = match ions {
+                    Some((cation, anion)) => {
+                        let is_acid = cation.composition().elements() == vec![Element::from_str("H")];
+                        let is_strong_acid = strong_acids.iter().any(|y| x.is_same_species(y));
+                        if is_strong_acid || (!is_acid && is_soluble(&cation, &anion)) {
+                            let co = x.root_coefficient();
+                            for y in &[cation, anion] {
+                                let count = &co * &y.root_coefficient();
+                                ionic.add_term(y.clone().with_coefficient(count));
+                            }
+                            x.clone().with_state(Some(State::Aq))
+                        } else if is_acid {
+                            ionic.0.push(x.clone());
+                            x.clone()
+                        } else {
+                            let solid = x.clone().with_state(Some(State::S));
+                            ionic.0.push(solid.clone());
+                            solid
+                        }
+                    }
+                    None => {
+                        ionic.0.push(x.clone());
+                        x.clone()
+                    }
+                };
+                molecular.push(node);
+            }
+            (molecular, ionic.0)
+        };
+        let (molecular_reactants, mut reactants) = split(&balanced.reactants);
+        let (molecular_products, mut products) = split(&balanced.products);
+        let molecular = Reaction {
+            reactants: Sequence(molecular_reactants),
+            products: Sequence(molecular_products),
+            ..balanced.clone()
+        };
+        let complete = Reaction {
+            reactants: Sequence(reactants.clone()),
+            products: Sequence(products.clone()),
+            ..balanced.clone()
+        };
+        // Cancel whatever appears unchanged on both sides.
+        let mut spectators = Vec::new();
+        for reactant in reactants.iter_mut() {
+            let matching = products
+                .iter_mut()
+                .find(|x| x.is_same_species(reactant) && x.state() == reactant.state());
+            if let Some(product) = matching {
+                let left = reactant.root_coefficient();
+                let right = product.root_coefficient();
+                let common = std::cmp::min(left.clone(), right.clone());
+                spectators.push(reactant.clone().with_coefficient(common.clone()));
+                *reactant = reactant.clone().with_coefficient(&left - &common);
+                *product = product.clone().with_coefficient(&right - &common);
+            }
+        }
+        let remaining = |xs: Vec<Node>| -> Vec<Node> {
+            xs.into_iter().filter(|x| !x.root_coefficient().is_zero()).collect()
+        };
+        let reactants = remaining(reactants);
+        let products = remaining(products);
+        if reactants.is_empty() || products.is_empty() {
+            return Err(IonicError::NoReaction)
+        }
+        let gcd = reactants
+            .iter()
+            .chain(products.iter())
+            .map(Node::root_coefficient)
+            .fold(Number::int(0), |acc, x| acc.gcd(&x));
+        let reduce = |xs: Vec<Node>| -> Sequence {
+            let xs = xs
+                .into_iter()
+                .map(|x| {
+                    let co = &x.root_coefficient() / &gcd;
+                    x.with_coefficient(co)
+                })
+                .collect();
+            Sequence(xs)
+        };
+        let net = Reaction {
+            reactants: reduce(reactants),
+            products: reduce(products),
+            ..balanced
+        };
+        Ok(IonicEquations {molecular, complete, net, spectators})
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dissociation() {
+        let run = |source: &str| {
+            dissociate(&Node::from_str(source).unwrap())
+                .map(|(cation, anion)| format!("{} + {}", cation.formula(), anion.formula()))
+        };
+        assert_eq!(run("NaCl"), Some(String::from("Na^+ + Cl^-")));
+        assert_eq!(run("Ca(NO3)2"), Some(String::from("Ca^2+ + NO3^-")));
+        assert_eq!(run("Na2SO4"), Some(String::from("Na^+ + SO4^2-")));
+        assert_eq!(run("(NH4)3PO4"), Some(String::from("NH4^+ + PO4^3-")));
+        assert_eq!(run("NH4Cl"), Some(String::from("NH4^+ + Cl^-")));
+        assert_eq!(run("Fe2O3"), Some(String::from("Fe^3+ + O^2-")));
+        assert_eq!(run("NaC2H3O2"), Some(String::from("Na^+ + CH3COO^-")));
+        assert_eq!(run("CH3COOH"), Some(String::from("H^+ + CH3COO^-")));
+        assert_eq!(run("CO2"), None);
+    }
+
+    /// e.g. `2I^-(aq) + Pb^2+(aq) -> PbI2(s)`
+    fn render(reaction: &Reaction) -> String {
+        let side = |xs: &Sequence| {
+            xs.0.iter()
+                .map(|x| {
+                    let co = x.root_coefficient();
+                    let co = if co == Number::int(1) {String::new()} else {format!("{}", co)};
+                    let state = x.state().map(|x| format!("({:?})", x).to_lowercase());
+                    format!("{}{}{}", co, x.formula(), state.unwrap_or_default())
+                })
+                .collect::<Vec<_>>()
+                .join(" + ")
+        };
+        format!("{} -> {}", side(&reaction.reactants), side(&reaction.products))
+    }
+
+    #[test]
+    fn net_ionic_equations() {
+        let run = |source: &str| {
+            let result = Reaction::from_str(source).unwrap().ionic_equations().unwrap();
+            (render(&result.molecular), render(&result.complete), render(&result.net))
+        };
+        let (molecular, complete, net) = run("AgNO3 + NaCl -> AgCl + NaNO3");
+        assert_eq!(molecular, "AgNO3(aq) + NaCl(aq) -> AgCl(s) + NaNO3(aq)");
+        assert_eq!(complete, "Ag^+(aq) + NO3^-(aq) + Na^+(aq) + Cl^-(aq) -> AgCl(s) + Na^+(aq) + NO3^-(aq)");
+        assert_eq!(net, "Ag^+(aq) + Cl^-(aq) -> AgCl(s)");
+        // Neutralisation; coefficients come from balancing.
+        let (molecular, _, net) = run("H2SO4(aq) + NaOH(aq) -> Na2SO4(aq) + H2O(l)");
+        assert_eq!(molecular, "H2SO4(aq) + 2NaOH(aq) -> Na2SO4(aq) + 2H2O(l)");
+        assert_eq!(net, "H^+(aq) + OH^-(aq) -> H2O(l)");
+        let (_, complete, net) = run("Pb(NO3)2 + KI -> PbI2 + KNO3");
+        assert_eq!(complete, "Pb^2+(aq) + 2NO3^-(aq) + 2K^+(aq) + 2I^-(aq) -> PbI2(s) + 2K^+(aq) + 2NO3^-(aq)");
+        assert_eq!(net, "Pb^2+(aq) + 2I^-(aq) -> PbI2(s)");
+        // A weak acid stays molecular.
+        let (_, _, net) = run("CH3COOH(aq) + NaOH(aq) -> NaCH3COO(aq) + H2O(l)");
+        assert_eq!(net, "CH3COOH(aq) + OH^-(aq) -> CH3COO^-(aq) + H2O(l)");
+        let result = Reaction::from_str("NaCl + KNO3 -> NaNO3 + KCl").unwrap().ionic_equations();
+        assert!(matches!(result, Err(IonicError::NoReaction)));
+    }
+}
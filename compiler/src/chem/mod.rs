@@ -1,2 +1,13 @@
+pub mod acidbase;
+pub mod algebra;
+pub mod classify;
 pub mod data;
-pub mod parser;
\ No newline at end of file
+pub mod empirical;
+pub mod equilibrium;
+pub mod ionic;
+pub mod oxidation;
+pub mod parser;
+pub mod periodic;
+pub mod redox;
+pub mod stoichiometry;
+pub mod thermo;
\ No newline at end of file
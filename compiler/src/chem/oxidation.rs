@@ -0,0 +1,305 @@
+//! Oxidation state assignment.
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::FromIterator;
+
+use crate::numbers::Number;
+use crate::chem::data::*;
+use crate::chem::periodic::ElementInfo;
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+/// Oxidation state of each element in a formula unit. For elements that occur
+/// in more than one environment this is the average (e.g. +8/3 for Fe in
+/// Fe₃O₄).
+pub type OxidationStates = BTreeMap<Element, Number>;
+
+#[derive(Debug, Clone)]
+pub enum OxidationError {
+    UnknownElement(Element),
+    /// The rules fix some elements, but either none or more than one
+    /// assignment of common oxidation states to the rest agrees with the
+    /// charge.
+    Ambiguous {
+        species: Box<Node>,
+        assigned: OxidationStates,
+        unknown: Vec<Element>,
+    },
+}
+
+impl std::fmt::Display for OxidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OxidationError::UnknownElement(x) => {
+                write!(f, "unknown element {}", x)
+            }
+            OxidationError::Ambiguous {species, unknown, ..} => {
+                let unknown = unknown
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(
+                    f,
+                    "oxidation states of {} in {} are ambiguous",
+                    unknown,
+                    species.to_string(),
+                )
+            }
+        }
+    }
+}
+
+/// An element whose oxidation state differs between a reactant and a product.
+#[derive(Debug, Clone)]
+pub struct OxidationChange {
+    pub element: Element,
+    pub reactant: Node,
+    pub product: Node,
+    pub from: Number,
+    pub to: Number,
+}
+
+impl OxidationChange {
+    pub fn is_oxidation(&self) -> bool {
+        self.to > self.from
+    }
+    pub fn is_reduction(&self) -> bool {
+        self.to < self.from
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// OXIDATION STATES
+///////////////////////////////////////////////////////////////////////////////
+
+impl Node {
+    /// Assigns oxidation states by the usual rules, in order of priority:
+    ///
+    /// 1. Free elements and monatomic ions carry their charge.
+    /// 2. F is −1, group 1 metals +1 and group 2 metals +2.
+    /// 3. H is +1, or −1 when bonded only to metals.
+    /// 4. O is −2.
+    /// 5. The states sum to the charge of the species.
+    ///
+    /// Rules 2–4 are only applied while another element is left to balance
+    /// the charge, which is what handles peroxides (H₂O₂), superoxides (KO₂),
+    /// OF₂ and hydrides (NaH). If more than one element is left over, we look
+    /// for the unique combination of their common oxidation states that
+    /// agrees with the charge.
+    pub fn oxidation_states(&self) -> Result<OxidationStates, OxidationError> {
+        let unit = self.clone().with_coefficient(Number::int(1));
+        let composition = unit.composition();
+        let elements = BTreeSet::<Element>::from_iter(composition.elements());
+        let count = |x: &Element| composition.get(x);
+        let charge = Number::from_isize(unit.charge());
+        for element in elements.iter() {
+            if element.info().is_none() {
+                return Err(OxidationError::UnknownElement(element.clone()))
+            }
+        }
+        let mut assigned = OxidationStates::new();
+        let mut unknown = elements.iter().cloned().collect::<Vec<_>>();
+        let hydrogen_state = {
+            let hydrogen = Element::from_str("H");
+            let is_hydride = elements
+                .iter()
+                .filter(|x| **x != hydrogen)
+                .all(|x| x.info().is_some_and(ElementInfo::is_metal));
+            if is_hydride {-1} else {1}
+        };
+        let rules = |x: &Element| -> Option<i8> {
+            let info = x.info()?;
+            match (info.symbol, info.group) {
+                ("F", _) => Some(-1),
+                ("H", _) => Some(hydrogen_state),
+                (_, Some(1)) => Some(1),
+                (_, Some(2)) => Some(2),
+                ("O", _) => Some(-2),
+                _ => None,
+            }
+        };
+        // The order matters; e.g. OF₂ and NaH are resolved by fixing F and Na
+        // first and solving for O and H.
+        let priority = |x: &Element| -> u8 {
+            match x.info().map(|x| (x.symbol, x.group)) {
+                Some(("F", _)) => 0,
+                Some(("H", _)) => 2,
+                Some((_, Some(1))) | Some((_, Some(2))) => 1,
+                Some(("O", _)) => 3,
+                _ => 4,
+            }
+        };
+        let mut ordered = unknown.clone();
+        ordered.sort_by_key(|x| priority(x));
+        for element in ordered {
+            if unknown.len() < 2 {
+                break;
+            }
+            if let Some(state) = rules(&element) {
+                assigned.insert(element.clone(), Number::int(state));
+                unknown.retain(|x| *x != element);
+            }
+        }
+        let remaining = &charge - &assigned
+            .iter()
+            .map(|(x, state)| state * &count(x))
+            .sum::<Number>();
+        match &unknown[..] {
+            [] => {}
+            [x] => {
+                assigned.insert(x.clone(), &remaining / &count(x));
+            }
+            xs => {
+                let candidates = xs
+                    .iter()
+                    .map(|x| x.info().unwrap().oxidation_states)
+                    .collect::<Vec<_>>();
+                let mut solutions = Vec::<Vec<i8>>::new();
+                for states in combinations(&candidates) {
+                    let total = xs
+                        .iter()
+                        .zip(states.iter())
+                        .map(|(x, state)| &Number::int(*state) * &count(x))
+                        .sum::<Number>();
+                    if total == remaining {
+                        solutions.push(states);
+                    }
+                }
+                match &solutions[..] {
+                    [states] => {
+                        for (x, state) in xs.iter().zip(states.iter()) {
+                            assigned.insert(x.clone(), Number::int(*state));
+                        }
+                    }
+                    _ => {
+                        return Err(OxidationError::Ambiguous {
+                            species: Box::new(unit),
+                            assigned,
+                            unknown,
+                        })
+                    }
+                }
+            }
+        }
+        Ok(assigned)
+    }
+}
+
+impl Reaction {
+    /// Every element whose oxidation state changes between a reactant and a
+    /// product that contain it. Free electrons are skipped.
+    pub fn oxidation_changes(&self) -> Result<Vec<OxidationChange>, OxidationError> {
+        let states = |xs: &Sequence| -> Result<Vec<(Node, OxidationStates)>, OxidationError> {
+            xs.0.iter()
+                .filter(|x| !x.is_electron())
+                .map(|x| Ok((x.clone(), x.oxidation_states()?)))
+                .collect()
+        };
+        let reactants = states(&self.reactants)?;
+        let products = states(&self.products)?;
+        let mut changes = Vec::new();
+        for (reactant, before) in reactants.iter() {
+            for (product, after) in products.iter() {
+                for (element, from) in before.iter() {
+                    match after.get(element) {
+                        Some(to) if to != from => {
+                            changes.push(OxidationChange {
+                                element: element.clone(),
+                                reactant: reactant.clone(),
+                                product: product.clone(),
+                                from: from.clone(),
+                                to: to.clone(),
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(changes)
+    }
+}
+
+fn combinations(candidates: &[&[i8]]) -> Vec<Vec<i8>> {
+    match candidates.split_first() {
+        None => vec![vec![]],
+        Some((first, rest)) => {
+            let rest = combinations(rest);
+            first
+                .iter()
+                .flat_map(|x| rest.iter().map(move |xs| {
+                    [&[*x], &xs[..]].concat()
+                }))
+                .collect()
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oxidation_states() {
+        let samples: &[(&str, &[(&str, Number)])] = &[
+            ("O2", &[("O", Number::int(0))]),
+            ("Fe^3+", &[("Fe", Number::int(3))]),
+            ("H2O", &[("H", Number::int(1)), ("O", Number::int(-2))]),
+            ("H2O2", &[("H", Number::int(1)), ("O", Number::int(-1))]),
+            ("KO2", &[("K", Number::int(1)), ("O", Number::fraction(-1, 2))]),
+            ("OF2", &[("F", Number::int(-1)), ("O", Number::int(2))]),
+            ("NaH", &[("Na", Number::int(1)), ("H", Number::int(-1))]),
+            ("CH4", &[("C", Number::int(-4)), ("H", Number::int(1))]),
+            ("PH3", &[("P", Number::int(-3)), ("H", Number::int(1))]),
+            ("AsH3", &[("As", Number::int(-3)), ("H", Number::int(1))]),
+            ("LiAlH4", &[("Li", Number::int(1)), ("Al", Number::int(3)), ("H", Number::int(-1))]),
+            ("MnO4-", &[("Mn", Number::int(7)), ("O", Number::int(-2))]),
+            ("Cr2O7^2-", &[("Cr", Number::int(6))]),
+            ("Fe3O4", &[("Fe", Number::fraction(8, 3))]),
+            ("FeCl3", &[("Fe", Number::int(3)), ("Cl", Number::int(-1))]),
+            ("CuSO4", &[("Cu", Number::int(2)), ("S", Number::int(6))]),
+        ];
+        for (source, expected) in samples {
+            let states = Node::from_str(source)
+                .unwrap()
+                .oxidation_states()
+                .unwrap();
+            for (element, state) in expected.iter() {
+                assert_eq!(
+                    states.get(&Element::from_str(element)),
+                    Some(state),
+                    "{} in {}",
+                    element,
+                    source,
+                );
+            }
+        }
+        // Co +2/Ti +4 and Co +3/Ti +3 both fit.
+        match Node::from_str("CoTiO3").unwrap().oxidation_states() {
+            Err(OxidationError::Ambiguous {unknown, ..}) => {
+                assert_eq!(unknown.len(), 2);
+            }
+            x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn oxidation_changes() {
+        let reaction = Reaction::from_str("Zn + CuSO4 -> ZnSO4 + Cu").unwrap();
+        let changes = reaction.oxidation_changes().unwrap();
+        assert_eq!(changes.len(), 2);
+        let oxidized = changes.iter().find(|x| x.is_oxidation()).unwrap();
+        let reduced = changes.iter().find(|x| x.is_reduction()).unwrap();
+        assert_eq!(oxidized.element, Element::from_str("Zn"));
+        assert_eq!(reduced.element, Element::from_str("Cu"));
+        assert_eq!((reduced.from.clone(), reduced.to.clone()), (Number::int(2), Number::int(0)));
+    }
+}
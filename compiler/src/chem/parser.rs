@@ -13,7 +13,7 @@ use nom::{
     branch::alt,
     character::complete::{alpha1},
     character::complete::alphanumeric1,
-    combinator::{cut, map, opt},
+    combinator::{cut, map, map_opt, opt, peek, verify},
     error::{context, VerboseError},
     multi::{many0, many1},
     sequence::{preceded, terminated},
@@ -70,64 +70,247 @@ pub fn parse_unit(source: &str) -> Result<(&str, Node), nom::Err<nom::error::Err
     );
     Ok((source, value))
 }
+/// A subscript, which is either a whole number or a decimal (`Fe0.95O`);
+/// one if omitted.
+///
+/// A `.` after a subscript of one or more and before another formula unit is
+/// a hydrate dot rather than a decimal point, so `CuSO4.5H2O` is read as
+/// `CuSO4·5H2O`. Decimal subscripts before another unit are thus less than
+/// one, as in `Fe0.95O`.
 pub fn parse_num(source: &str) -> Result<(&str, Number), nom::Err<nom::error::Error<&str>>> {
-    let (source, mut subscript) = take_while(|x: char| {
-        match x {
-            '0' => true,
-            '1' => true,
-            '2' => true,
-            '3' => true,
-            '4' => true,
-            '5' => true,
-            '6' => true,
-            '7' => true,
-            '8' => true,
-            '9' => true,
-            _ => false,
-        }
-    })(source)?;
-    if subscript.is_empty() {
-        subscript = "1";
+    let (rest, whole) = opt(digit1)(source)?;
+    let whole = match whole {
+        Some(x) => x,
+        None => return Ok((rest, Number::int(1))),
+    };
+    let (after, decimals) = opt(recognize(pair(char('.'), digit1)))(rest)?;
+    let starts_unit = after.starts_with(|c: char| c.is_uppercase() || c == '(' || c == '[');
+    let is_hydrate_dot = decimals.is_some() && starts_unit && whole.chars().any(|c| c != '0');
+    if decimals.is_none() || is_hydrate_dot {
+        return Ok((rest, Number::decimal(whole).unwrap()))
     }
-    let number = subscript.parse::<i128>().unwrap();
-    let number = Number::int(number);
+    let digits = &source[..source.len() - after.len()];
+    Ok((after, Number::decimal(digits).unwrap()))
+}
+/// The multiplier of an adduct part, e.g. the `5` in `CuSO4·5H2O` or the
+/// `0.5` in `CaSO4·0.5H2O`; one if omitted.
+fn parse_multiplier(source: &str) -> Result<(&str, Number), nom::Err<nom::error::Error<&str>>> {
+    let (source, digits) = opt(recognize(pair(digit1, opt(pair(char('.'), digit1)))))(source)?;
+    let number = digits
+        .and_then(Number::decimal)
+        .unwrap_or(Number::int(1));
     Ok((source, number))
 }
+/// A coefficient, which may be a decimal or a fraction (`1/2 O2`), along with
+/// any whitespace that separates it from the formula.
+pub fn parse_coefficient(source: &str) -> Result<(&str, Number), nom::Err<nom::error::Error<&str>>> {
+    fn fraction(source: &str) -> Result<(&str, Number), nom::Err<nom::error::Error<&str>>> {
+        let (source, numerator) = digit1(source)?;
+        let (source, _) = char('/')(source)?;
+        let (source, denominator) = verify(digit1, |x: &str| {
+            x.chars().any(|c| c != '0')
+        })(source)?;
+        let value = &Number::decimal(numerator).unwrap() / &Number::decimal(denominator).unwrap();
+        Ok((source, value))
+    }
+    fn decimal(source: &str) -> Result<(&str, Number), nom::Err<nom::error::Error<&str>>> {
+        let (source, digits) = recognize(pair(digit1, opt(pair(char('.'), digit1))))(source)?;
+        Ok((source, Number::decimal(digits).unwrap()))
+    }
+    terminated(alt((fraction, decimal)), multispace0)(source)
+}
+/// A group in round or square brackets, which may itself contain groups
+/// (e.g. `K4[Fe(CN)6]` or `((CH3)3C)2O`).
 pub fn parse_parens(source: &str) -> Result<(&str, Node), nom::Err<nom::error::Error<&str>>> {
     fn inner_parser(source: &str) -> Result<(&str, Vec<Node>), nom::Err<nom::error::Error<&str>>> {
-        many1(parse_unit)(source)
+        many1(alt((parse_parens, parse_unit)))(source)
     }
-    let (source, xs) = parens(inner_parser)(source)?;
+    let round = map(parens(inner_parser), |xs| (xs, Bracket::Round));
+    let square = map(
+        delimited(char('['), inner_parser, char(']')),
+        |xs| (xs, Bracket::Square),
+    );
+    let (source, (xs, bracket)) = alt((round, square))(source)?;
     let (source, subscript) = parse_num(source)?;
-    let value = Node::Parens(xs, subscript);
+    let value = Node::Parens(xs, subscript, bracket);
     Ok((source, value))
 }
-pub fn parse_group(source: &str) -> Result<(&str, Node), nom::Err<nom::error::Error<&str>>> {
-    let (source, coefficient) = opt(parse_num)(source)?;
+/// Ionic charge, written with a caret (`^2-`, `^-2`, `^+`), in superscript
+/// (`²⁻`, `⁺`), or as a bare trailing sign (`NH4+`, `Cl-`).
+///
+/// A bare sign only counts as a charge when followed by whitespace, a state or
+/// the end of input, so `H2+O2` is still read as a sum.
+pub fn parse_charge(source: &str) -> Result<(&str, Charge), nom::Err<nom::error::Error<&str>>> {
+    /// The digits of the magnitude, if any, and the sign.
+    type Parts = (Option<String>, char);
+    fn to_charge((digits, sign): Parts) -> Option<Charge> {
+        let magnitude = match digits {
+            Some(x) => x.parse::<Charge>().ok()?,
+            None => 1,
+        };
+        match sign {
+            '-' | '⁻' => Some(-magnitude),
+            _ => Some(magnitude),
+        }
+    }
+    fn caret(source: &str) -> Result<(&str, Parts), nom::Err<nom::error::Error<&str>>> {
+        let (source, _) = char('^')(source)?;
+        let (source, before) = opt(digit1)(source)?;
+        let (source, sign) = one_of("+-")(source)?;
+        let (source, after) = match before {
+            Some(_) => (source, None),
+            None => opt(digit1)(source)?,
+        };
+        Ok((source, (before.or(after).map(String::from), sign)))
+    }
+    fn superscript(source: &str) -> Result<(&str, Parts), nom::Err<nom::error::Error<&str>>> {
+        let (source, digits) = take_while(|c: char| "⁰¹²³⁴⁵⁶⁷⁸⁹".contains(c))(source)?;
+        let (source, sign) = one_of("⁺⁻")(source)?;
+        let digits = digits
+            .chars()
+            .map(|c| "⁰¹²³⁴⁵⁶⁷⁸⁹".chars().position(|x| x == c).unwrap())
+            .map(|x| std::char::from_digit(x as u32, 10).unwrap())
+            .collect::<String>();
+        Ok((source, (Some(digits).filter(|x| !x.is_empty()), sign)))
+    }
+    fn bare(source: &str) -> Result<(&str, Parts), nom::Err<nom::error::Error<&str>>> {
+        let (rest, sign) = one_of("+-")(source)?;
+        match rest.chars().next() {
+            None => Ok((rest, (None, sign))),
+            Some(c) if c.is_whitespace() || c == '(' => Ok((rest, (None, sign))),
+            Some(_) => Err(nom::Err::Error(nom::error::Error::new(
+                source,
+                nom::error::ErrorKind::Verify,
+            ))),
+        }
+    }
+    // Once a charge is written, a magnitude too large for `Charge` fails the
+    // whole formula rather than leaving it uncharged.
+    let (_, _) = peek(alt((caret, superscript, bare)))(source)?;
+    cut(map_opt(alt((caret, superscript, bare)), to_charge))(source)
+}
+
+/// The formula units of a group along with their net charge.
+type FormulaBody = (Vec<Node>, Charge);
+
+/// A free electron, i.e. `e-`, `e^-` or `e⁻`.
+fn parse_electron(source: &str) -> Result<(&str, FormulaBody), nom::Err<nom::error::Error<&str>>> {
+    let (source, _) = char('e')(source)?;
+    let (source, charge) = verify(parse_charge, |x: &Charge| *x == -1)(source)?;
+    Ok((source, (Vec::new(), charge)))
+}
+
+/// A multiplier and the formula units it applies to.
+type AdductPart = (Number, Vec<Node>);
+
+/// A single part of an adduct, e.g. `5H2O` in `CuSO4·5H2O`.
+fn parse_adduct_part(source: &str) -> Result<(&str, AdductPart), nom::Err<nom::error::Error<&str>>> {
+    let (source, multiplier) = parse_multiplier(source)?;
     let (source, values) = many1(alt((parse_parens, parse_unit)))(source)?;
+    Ok((source, (multiplier, values)))
+}
+
+/// Formula units, optionally dot-joined into an adduct (`CuSO4·5H2O`,
+/// `CuSO4*5H2O` or `CuSO4.5H2O`; see `parse_num` for the last).
+fn parse_formula(source: &str) -> Result<(&str, FormulaBody), nom::Err<nom::error::Error<&str>>> {
+    let (source, head) = many1(alt((parse_parens, parse_unit)))(source)?;
+    let (source, rest) = many0(preceded(one_of("·*."), parse_adduct_part))(source)?;
+    let values = if rest.is_empty() {
+        head
+    } else {
+        let mut parts = vec![(Number::int(1), head)];
+        parts.extend(rest);
+        vec![Node::Adduct(parts)]
+    };
+    let (source, charge) = opt(parse_charge)(source)?;
+    Ok((source, (values, charge.unwrap_or(0))))
+}
+
+pub fn parse_group(source: &str) -> Result<(&str, Node), nom::Err<nom::error::Error<&str>>> {
+    let (source, coefficient) = opt(parse_coefficient)(source)?;
+    let (source, (values, charge)) = alt((parse_electron, parse_formula))(source)?;
     let (source, state) = opt(parse_state)(source)?;
-    let value = Node::Chunk(coefficient.unwrap_or(Number::int(1)), values, state);
+    let value = Node::Chunk(coefficient.unwrap_or(Number::int(1)), values, charge, state);
     Ok((source, value))
 }
 
+/// Like `parse_group`, but rejects element symbols that aren't in the
+/// periodic table (e.g. `Xy`).
+pub fn parse_group_strict(source: &str) -> Result<(&str, Node), nom::Err<nom::error::Error<&str>>> {
+    verify(parse_group, |node: &Node| {
+        node.atoms().iter().all(Element::is_known)
+    })(source)
+}
+
+type GroupParser = for<'a> fn(&'a str) -> Result<(&'a str, Node), nom::Err<nom::error::Error<&'a str>>>;
+
+fn parse_sum_with(group: GroupParser, source: &str) -> Result<(&str, Vec<Node>), nom::Err<nom::error::Error<&str>>> {
+    separated_list1(ws(tag("+")), group)(source)
+}
+
 pub fn parse_sum(source: &str) -> Result<(&str, Vec<Node>), nom::Err<nom::error::Error<&str>>> {
-    fn inner_parser(source: &str) -> Result<(&str, Vec<Node>), nom::Err<nom::error::Error<&str>>> {
-        separated_list1(ws(tag("+")), parse_group)(source)
-    }
-    inner_parser(source)
+    parse_sum_with(parse_group, source)
 }
 
-pub fn parse_reaction(source: &str) -> Result<(&str, Reaction), nom::Err<nom::error::Error<&str>>> {
-    let (source, left) = parse_sum(source)?;
-    let (source, _) = ws(tag("->"))(source)?;
-    let (source, right) = parse_sum(source)?;
+pub fn parse_sum_strict(source: &str) -> Result<(&str, Vec<Node>), nom::Err<nom::error::Error<&str>>> {
+    parse_sum_with(parse_group_strict, source)
+}
+
+/// An annotation over the arrow: heat, light or a catalyst.
+fn parse_condition(source: &str) -> Result<(&str, Condition), nom::Err<nom::error::Error<&str>>> {
+    let heat = map(alt((tag("Δ"), tag("heat"))), |_| Condition::Heat);
+    let light = map(alt((tag("hν"), tag("hv"))), |_| Condition::Light);
+    let catalyst = map(parse_group, Condition::Catalyst);
+    alt((heat, light, catalyst))(source)
+}
+
+/// The kind of arrow along with the conditions written over it.
+type ArrowBody = (Arrow, Vec<Condition>);
+
+/// `->`, `→`, `=`, `<-`, `←`, `<=>` or `⇌`, optionally followed (without
+/// whitespace) by conditions in square brackets, e.g. `->[Δ, Pt]`.
+pub fn parse_arrow(source: &str) -> Result<(&str, ArrowBody), nom::Err<nom::error::Error<&str>>> {
+    let equilibrium = map(alt((tag("<=>"), tag("⇌"))), |_| Arrow::Equilibrium);
+    let backward = map(alt((tag("<-"), tag("←"))), |_| Arrow::Backward);
+    let forward = map(alt((tag("->"), tag("→"), tag("="))), |_| Arrow::Forward);
+    let (source, arrow) = alt((equilibrium, backward, forward))(source)?;
+    let conditions = delimited(
+        char('['),
+        separated_list1(ws(char(',')), ws(parse_condition)),
+        char(']'),
+    );
+    let (source, conditions) = opt(conditions)(source)?;
+    Ok((source, (arrow, conditions.unwrap_or_default())))
+}
+
+fn parse_reaction_with(group: GroupParser, source: &str) -> Result<(&str, Reaction), nom::Err<nom::error::Error<&str>>> {
+    let (source, left) = parse_sum_with(group, source)?;
+    let (source, (arrow, conditions)) = ws(parse_arrow)(source)?;
+    let (source, right) = parse_sum_with(group, source)?;
+    // The products of `<-` are on the left; only the rendering keeps them
+    // there.
+    let (reactants, products) = match arrow {
+        Arrow::Backward => (right, left),
+        _ => (left, right),
+    };
     let reaction = Reaction {
-        reactants: Sequence(left),
-        products: Sequence(right),
+        reactants: Sequence(reactants),
+        products: Sequence(products),
+        arrow,
+        conditions,
     };
     Ok((source, reaction))
 }
 
+pub fn parse_reaction(source: &str) -> Result<(&str, Reaction), nom::Err<nom::error::Error<&str>>> {
+    parse_reaction_with(parse_group, source)
+}
+
+/// Like `parse_reaction`, but rejects unknown element symbols.
+pub fn parse_reaction_strict(source: &str) -> Result<(&str, Reaction), nom::Err<nom::error::Error<&str>>> {
+    parse_reaction_with(parse_group_strict, source)
+}
+
 
 pub fn run() {
     let source = "Ca(O3H2)2(aq) + 2HCl(aq) -> CaCl2(aq) + H2O(l)";
@@ -0,0 +1,240 @@
+//! Built-in periodic table.
+use crate::numbers::Number;
+use crate::chem::data::Element;
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementInfo {
+    pub atomic_number: u8,
+    pub symbol: &'static str,
+    pub name: &'static str,
+    /// Conventional standard atomic weight in g/mol, kept as written so it can
+    /// be converted exactly. For elements without a standard atomic weight,
+    /// this is the mass number of the longest-lived isotope.
+    pub atomic_weight: &'static str,
+    /// `None` for the lanthanides and actinides (other than Lu and Lr).
+    pub group: Option<u8>,
+    pub period: u8,
+    /// The main oxidation states; empty for noble gases and elements whose
+    /// chemistry is unknown.
+    pub oxidation_states: &'static [i8],
+    /// Pauling scale; `None` where there is no established value.
+    pub electronegativity: Option<f64>,
+}
+
+impl ElementInfo {
+    pub fn from_symbol(symbol: &str) -> Option<&'static ElementInfo> {
+        PERIODIC_TABLE.iter().find(|x| x.symbol == symbol)
+    }
+    pub fn from_atomic_number(atomic_number: u8) -> Option<&'static ElementInfo> {
+        PERIODIC_TABLE.get((atomic_number as usize).checked_sub(1)?)
+    }
+    pub fn all() -> &'static [ElementInfo] {
+        &PERIODIC_TABLE
+    }
+    /// Standard atomic weight in g/mol, as an exact `Number`.
+    pub fn atomic_weight(&self) -> Number {
+        Number::decimal(self.atomic_weight).unwrap()
+    }
+    pub fn element(&self) -> Element {
+        Element::from_str(self.symbol)
+    }
+    /// Groups 1–13 (other than H and B), the lanthanides and actinides, and
+    /// the heavier metals of groups 14–16 (Sn, Pb, Bi, Po).
+    pub fn is_metal(&self) -> bool {
+        match (self.symbol, self.group) {
+            ("H", _) | ("B", _) => false,
+            (_, None) => true,
+            (_, Some(1..=13)) => true,
+            (symbol, _) => matches!(symbol, "Sn" | "Pb" | "Bi" | "Po"),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// TABLE
+///////////////////////////////////////////////////////////////////////////////
+
+#[allow(clippy::too_many_arguments)]
+const fn element(
+    atomic_number: u8,
+    symbol: &'static str,
+    name: &'static str,
+    atomic_weight: &'static str,
+    group: Option<u8>,
+    period: u8,
+    oxidation_states: &'static [i8],
+    electronegativity: Option<f64>,
+) -> ElementInfo {
+    ElementInfo {
+        atomic_number,
+        symbol,
+        name,
+        atomic_weight,
+        group,
+        period,
+        oxidation_states,
+        electronegativity,
+    }
+}
+
+/// Ordered by atomic number.
+static PERIODIC_TABLE: [ElementInfo; 118] = [
+    element(1, "H", "Hydrogen", "1.008", Some(1), 1, &[-1, 1], Some(2.20)),
+    element(2, "He", "Helium", "4.0026", Some(18), 1, &[], None),
+    element(3, "Li", "Lithium", "6.94", Some(1), 2, &[1], Some(0.98)),
+    element(4, "Be", "Beryllium", "9.0122", Some(2), 2, &[2], Some(1.57)),
+    element(5, "B", "Boron", "10.81", Some(13), 2, &[3], Some(2.04)),
+    element(6, "C", "Carbon", "12.011", Some(14), 2, &[-4, 2, 4], Some(2.55)),
+    element(7, "N", "Nitrogen", "14.007", Some(15), 2, &[-3, 3, 5], Some(3.04)),
+    element(8, "O", "Oxygen", "15.999", Some(16), 2, &[-2], Some(3.44)),
+    element(9, "F", "Fluorine", "18.998", Some(17), 2, &[-1], Some(3.98)),
+    element(10, "Ne", "Neon", "20.180", Some(18), 2, &[], None),
+    element(11, "Na", "Sodium", "22.990", Some(1), 3, &[1], Some(0.93)),
+    element(12, "Mg", "Magnesium", "24.305", Some(2), 3, &[2], Some(1.31)),
+    element(13, "Al", "Aluminium", "26.982", Some(13), 3, &[3], Some(1.61)),
+    element(14, "Si", "Silicon", "28.085", Some(14), 3, &[-4, 4], Some(1.90)),
+    element(15, "P", "Phosphorus", "30.974", Some(15), 3, &[-3, 3, 5], Some(2.19)),
+    element(16, "S", "Sulfur", "32.06", Some(16), 3, &[-2, 2, 4, 6], Some(2.58)),
+    element(17, "Cl", "Chlorine", "35.45", Some(17), 3, &[-1, 1, 3, 5, 7], Some(3.16)),
+    element(18, "Ar", "Argon", "39.948", Some(18), 3, &[], None),
+    element(19, "K", "Potassium", "39.098", Some(1), 4, &[1], Some(0.82)),
+    element(20, "Ca", "Calcium", "40.078", Some(2), 4, &[2], Some(1.00)),
+    element(21, "Sc", "Scandium", "44.956", Some(3), 4, &[3], Some(1.36)),
+    element(22, "Ti", "Titanium", "47.867", Some(4), 4, &[2, 3, 4], Some(1.54)),
+    element(23, "V", "Vanadium", "50.942", Some(5), 4, &[2, 3, 4, 5], Some(1.63)),
+    element(24, "Cr", "Chromium", "51.996", Some(6), 4, &[2, 3, 6], Some(1.66)),
+    element(25, "Mn", "Manganese", "54.938", Some(7), 4, &[2, 3, 4, 6, 7], Some(1.55)),
+    element(26, "Fe", "Iron", "55.845", Some(8), 4, &[2, 3], Some(1.83)),
+    element(27, "Co", "Cobalt", "58.933", Some(9), 4, &[2, 3], Some(1.88)),
+    element(28, "Ni", "Nickel", "58.693", Some(10), 4, &[2], Some(1.91)),
+    element(29, "Cu", "Copper", "63.546", Some(11), 4, &[1, 2], Some(1.90)),
+    element(30, "Zn", "Zinc", "65.38", Some(12), 4, &[2], Some(1.65)),
+    element(31, "Ga", "Gallium", "69.723", Some(13), 4, &[3], Some(1.81)),
+    element(32, "Ge", "Germanium", "72.630", Some(14), 4, &[-4, 2, 4], Some(2.01)),
+    element(33, "As", "Arsenic", "74.922", Some(15), 4, &[-3, 3, 5], Some(2.18)),
+    element(34, "Se", "Selenium", "78.971", Some(16), 4, &[-2, 4, 6], Some(2.55)),
+    element(35, "Br", "Bromine", "79.904", Some(17), 4, &[-1, 1, 3, 5], Some(2.96)),
+    element(36, "Kr", "Krypton", "83.798", Some(18), 4, &[2], Some(3.00)),
+    element(37, "Rb", "Rubidium", "85.468", Some(1), 5, &[1], Some(0.82)),
+    element(38, "Sr", "Strontium", "87.62", Some(2), 5, &[2], Some(0.95)),
+    element(39, "Y", "Yttrium", "88.906", Some(3), 5, &[3], Some(1.22)),
+    element(40, "Zr", "Zirconium", "91.224", Some(4), 5, &[4], Some(1.33)),
+    element(41, "Nb", "Niobium", "92.906", Some(5), 5, &[5], Some(1.6)),
+    element(42, "Mo", "Molybdenum", "95.95", Some(6), 5, &[4, 6], Some(2.16)),
+    element(43, "Tc", "Technetium", "98", Some(7), 5, &[4, 7], Some(1.9)),
+    element(44, "Ru", "Ruthenium", "101.07", Some(8), 5, &[3, 4], Some(2.2)),
+    element(45, "Rh", "Rhodium", "102.91", Some(9), 5, &[3], Some(2.28)),
+    element(46, "Pd", "Palladium", "106.42", Some(10), 5, &[2, 4], Some(2.20)),
+    element(47, "Ag", "Silver", "107.87", Some(11), 5, &[1], Some(1.93)),
+    element(48, "Cd", "Cadmium", "112.41", Some(12), 5, &[2], Some(1.69)),
+    element(49, "In", "Indium", "114.82", Some(13), 5, &[3], Some(1.78)),
+    element(50, "Sn", "Tin", "118.71", Some(14), 5, &[2, 4], Some(1.96)),
+    element(51, "Sb", "Antimony", "121.76", Some(15), 5, &[-3, 3, 5], Some(2.05)),
+    element(52, "Te", "Tellurium", "127.60", Some(16), 5, &[-2, 4, 6], Some(2.1)),
+    element(53, "I", "Iodine", "126.90", Some(17), 5, &[-1, 1, 3, 5, 7], Some(2.66)),
+    element(54, "Xe", "Xenon", "131.29", Some(18), 5, &[2, 4, 6], Some(2.60)),
+    element(55, "Cs", "Caesium", "132.91", Some(1), 6, &[1], Some(0.79)),
+    element(56, "Ba", "Barium", "137.33", Some(2), 6, &[2], Some(0.89)),
+    element(57, "La", "Lanthanum", "138.91", None, 6, &[3], Some(1.10)),
+    element(58, "Ce", "Cerium", "140.12", None, 6, &[3, 4], Some(1.12)),
+    element(59, "Pr", "Praseodymium", "140.91", None, 6, &[3], Some(1.13)),
+    element(60, "Nd", "Neodymium", "144.24", None, 6, &[3], Some(1.14)),
+    element(61, "Pm", "Promethium", "145", None, 6, &[3], None),
+    element(62, "Sm", "Samarium", "150.36", None, 6, &[2, 3], Some(1.17)),
+    element(63, "Eu", "Europium", "151.96", None, 6, &[2, 3], None),
+    element(64, "Gd", "Gadolinium", "157.25", None, 6, &[3], Some(1.20)),
+    element(65, "Tb", "Terbium", "158.93", None, 6, &[3], None),
+    element(66, "Dy", "Dysprosium", "162.50", None, 6, &[3], Some(1.22)),
+    element(67, "Ho", "Holmium", "164.93", None, 6, &[3], Some(1.23)),
+    element(68, "Er", "Erbium", "167.26", None, 6, &[3], Some(1.24)),
+    element(69, "Tm", "Thulium", "168.93", None, 6, &[3], Some(1.25)),
+    element(70, "Yb", "Ytterbium", "173.05", None, 6, &[2, 3], None),
+    element(71, "Lu", "Lutetium", "174.97", Some(3), 6, &[3], Some(1.27)),
+    element(72, "Hf", "Hafnium", "178.49", Some(4), 6, &[4], Some(1.3)),
+    element(73, "Ta", "Tantalum", "180.95", Some(5), 6, &[5], Some(1.5)),
+    element(74, "W", "Tungsten", "183.84", Some(6), 6, &[4, 6], Some(2.36)),
+    element(75, "Re", "Rhenium", "186.21", Some(7), 6, &[4, 7], Some(1.9)),
+    element(76, "Os", "Osmium", "190.23", Some(8), 6, &[4], Some(2.2)),
+    element(77, "Ir", "Iridium", "192.22", Some(9), 6, &[3, 4], Some(2.20)),
+    element(78, "Pt", "Platinum", "195.08", Some(10), 6, &[2, 4], Some(2.28)),
+    element(79, "Au", "Gold", "196.97", Some(11), 6, &[1, 3], Some(2.54)),
+    element(80, "Hg", "Mercury", "200.59", Some(12), 6, &[1, 2], Some(2.00)),
+    element(81, "Tl", "Thallium", "204.38", Some(13), 6, &[1, 3], Some(1.62)),
+    element(82, "Pb", "Lead", "207.2", Some(14), 6, &[2, 4], Some(2.33)),
+    element(83, "Bi", "Bismuth", "208.98", Some(15), 6, &[3, 5], Some(2.02)),
+    element(84, "Po", "Polonium", "209", Some(16), 6, &[-2, 2, 4], Some(2.0)),
+    element(85, "At", "Astatine", "210", Some(17), 6, &[-1, 1], Some(2.2)),
+    element(86, "Rn", "Radon", "222", Some(18), 6, &[2], Some(2.2)),
+    element(87, "Fr", "Francium", "223", Some(1), 7, &[1], Some(0.7)),
+    element(88, "Ra", "Radium", "226", Some(2), 7, &[2], Some(0.9)),
+    element(89, "Ac", "Actinium", "227", None, 7, &[3], Some(1.1)),
+    element(90, "Th", "Thorium", "232.04", None, 7, &[4], Some(1.3)),
+    element(91, "Pa", "Protactinium", "231.04", None, 7, &[5], Some(1.5)),
+    element(92, "U", "Uranium", "238.03", None, 7, &[3, 4, 5, 6], Some(1.38)),
+    element(93, "Np", "Neptunium", "237", None, 7, &[5], Some(1.36)),
+    element(94, "Pu", "Plutonium", "244", None, 7, &[4], Some(1.28)),
+    element(95, "Am", "Americium", "243", None, 7, &[3], Some(1.13)),
+    element(96, "Cm", "Curium", "247", None, 7, &[3], Some(1.28)),
+    element(97, "Bk", "Berkelium", "247", None, 7, &[3], Some(1.3)),
+    element(98, "Cf", "Californium", "251", None, 7, &[3], Some(1.3)),
+    element(99, "Es", "Einsteinium", "252", None, 7, &[3], Some(1.3)),
+    element(100, "Fm", "Fermium", "257", None, 7, &[3], Some(1.3)),
+    element(101, "Md", "Mendelevium", "258", None, 7, &[3], Some(1.3)),
+    element(102, "No", "Nobelium", "259", None, 7, &[2], Some(1.3)),
+    element(103, "Lr", "Lawrencium", "266", Some(3), 7, &[3], None),
+    element(104, "Rf", "Rutherfordium", "267", Some(4), 7, &[4], None),
+    element(105, "Db", "Dubnium", "268", Some(5), 7, &[5], None),
+    element(106, "Sg", "Seaborgium", "269", Some(6), 7, &[6], None),
+    element(107, "Bh", "Bohrium", "270", Some(7), 7, &[7], None),
+    element(108, "Hs", "Hassium", "269", Some(8), 7, &[8], None),
+    element(109, "Mt", "Meitnerium", "278", Some(9), 7, &[], None),
+    element(110, "Ds", "Darmstadtium", "281", Some(10), 7, &[], None),
+    element(111, "Rg", "Roentgenium", "282", Some(11), 7, &[], None),
+    element(112, "Cn", "Copernicium", "285", Some(12), 7, &[2], None),
+    element(113, "Nh", "Nihonium", "286", Some(13), 7, &[], None),
+    element(114, "Fl", "Flerovium", "289", Some(14), 7, &[], None),
+    element(115, "Mc", "Moscovium", "290", Some(15), 7, &[], None),
+    element(116, "Lv", "Livermorium", "293", Some(16), 7, &[], None),
+    element(117, "Ts", "Tennessine", "294", Some(17), 7, &[], None),
+    element(118, "Og", "Oganesson", "294", Some(18), 7, &[], None),
+];
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_lookups() {
+        for (ix, info) in ElementInfo::all().iter().enumerate() {
+            assert_eq!(info.atomic_number as usize, ix + 1);
+            assert_eq!(ElementInfo::from_symbol(info.symbol), Some(info));
+            assert!(info.atomic_weight() > Number::int(0), "{}", info.symbol);
+        }
+        let iron = ElementInfo::from_atomic_number(26).unwrap();
+        assert_eq!(iron.symbol, "Fe");
+        assert_eq!(iron.atomic_weight(), Number::fraction(55845, 1000));
+        assert_eq!(ElementInfo::from_atomic_number(0), None);
+        assert_eq!(ElementInfo::from_atomic_number(119), None);
+        assert_eq!(ElementInfo::from_symbol("Xy"), None);
+    }
+
+    #[test]
+    fn strict_parsing() {
+        use crate::chem::data::{Node, Reaction};
+        assert!(Node::from_str_strict("Fe2O3").is_some());
+        assert!(Node::from_str_strict("Xy2O3").is_none());
+        assert!(Node::from_str("Xy2O3").is_some());
+        assert!(Reaction::from_str_strict("Na + Cl2 -> NaCl").is_some());
+        assert!(Reaction::from_str_strict("2XY2 + 4Y -> 8X2 + 8YX").is_none());
+    }
+}
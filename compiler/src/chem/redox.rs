@@ -0,0 +1,456 @@
+//! Redox balancing by the half-reaction method.
+use std::collections::BTreeSet;
+use std::iter::FromIterator;
+
+use crate::numbers::Number;
+use crate::chem::data::*;
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Medium {
+    Acidic,
+    Basic,
+}
+
+impl Medium {
+    /// Species that may be added to either side of a half-reaction.
+    fn species(&self) -> Vec<Node> {
+        let xs = match self {
+            Medium::Acidic => ["H2O", "H+", "e-"],
+            Medium::Basic => ["H2O", "OH-", "e-"],
+        };
+        xs.iter()
+            .map(|x| Node::from_str(x).unwrap())
+            .collect()
+    }
+    fn describe(&self) -> &'static str {
+        match self {
+            Medium::Acidic => "H₂O, H⁺ and e⁻ (acidic)",
+            Medium::Basic => "H₂O, OH⁻ and e⁻ (basic)",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub description: String,
+    pub reaction: Reaction,
+}
+
+#[derive(Debug, Clone)]
+pub struct Redox {
+    /// Balanced oxidation half-reaction (electrons on the product side).
+    pub oxidation: Reaction,
+    /// Balanced reduction half-reaction (electrons on the reactant side).
+    pub reduction: Reaction,
+    pub overall: Reaction,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+pub enum RedoxError {
+    /// The species couldn't be grouped into an oxidation and a reduction
+    /// half-reaction.
+    CannotSplit,
+    /// A half-reaction couldn't be balanced, even with the species the medium
+    /// allows us to add.
+    Unbalanced {
+        half: Box<Reaction>,
+        error: BalanceError,
+    },
+    /// The halves don't transfer electrons between one another.
+    NoElectronTransfer,
+    /// The species split into this many half-reactions (e.g. when two of them
+    /// are oxidized), but only one oxidation and one reduction are supported.
+    TooManyHalves(usize),
+}
+
+impl std::fmt::Display for RedoxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RedoxError::CannotSplit => {
+                write!(f, "cannot split the reaction into oxidation and reduction half-reactions")
+            }
+            RedoxError::Unbalanced {half, error} => {
+                write!(f, "cannot balance {}: {}", half.to_string(), error)
+            }
+            RedoxError::NoElectronTransfer => {
+                write!(f, "not a redox reaction; no electrons are transferred")
+            }
+            RedoxError::TooManyHalves(n) => {
+                write!(f, "the reaction splits into {} half-reactions rather than two", n)
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// REDOX
+///////////////////////////////////////////////////////////////////////////////
+
+impl Reaction {
+    /// Balances a redox reaction by the half-reaction method, adding water,
+    /// electrons and either H⁺ or OH⁻ (depending on the medium) as needed.
+    ///
+    /// Any water, H⁺, OH⁻ or electrons in the given reaction are ignored, since
+    /// the balancer decides where those go.
+    pub fn balance_redox(&self, medium: Medium) -> Result<Redox, RedoxError> {
+        let mut steps = Vec::<Step>::new();
+        let mut oxidation = None::<(Reaction, Reaction, Number)>;
+        let mut reduction = None::<(Reaction, Reaction, Number)>;
+        let halves = split(self)?;
+        if halves.len() > 2 {
+            return Err(RedoxError::TooManyHalves(halves.len()))
+        }
+        for half in halves {
+            let balanced = balance_half(&half, medium)?;
+            let (left, right) = electrons(&balanced);
+            if right > Number::int(0) && oxidation.is_none() {
+                oxidation = Some((half, balanced, right));
+            } else if left > Number::int(0) && reduction.is_none() {
+                reduction = Some((half, balanced, left));
+            } else {
+                return Err(RedoxError::NoElectronTransfer)
+            }
+        }
+        let (ox_half, oxidation, ox_electrons) = oxidation.ok_or(RedoxError::NoElectronTransfer)?;
+        let (red_half, reduction, red_electrons) = reduction.ok_or(RedoxError::NoElectronTransfer)?;
+        let mut step = |description: String, reaction: &Reaction| {
+            steps.push(Step {description, reaction: reaction.clone()});
+        };
+        step(String::from("oxidation half-reaction"), &ox_half);
+        step(String::from("reduction half-reaction"), &red_half);
+        step(format!("balance the oxidation half-reaction with {}", medium.describe()), &oxidation);
+        step(format!("balance the reduction half-reaction with {}", medium.describe()), &reduction);
+        // Equalize the electrons lost and gained.
+        let gcd = ox_electrons.gcd(&red_electrons);
+        let ox_factor = &red_electrons / &gcd;
+        let red_factor = &ox_electrons / &gcd;
+        let ox_scaled = scale(&oxidation, &ox_factor);
+        let red_scaled = scale(&reduction, &red_factor);
+        step(format!("multiply the oxidation half-reaction by {}", ox_factor), &ox_scaled);
+        step(format!("multiply the reduction half-reaction by {}", red_factor), &red_scaled);
+        let order = self
+            .merge()
+            .0
+            .into_iter()
+            .chain(medium.species())
+            .collect::<Vec<_>>();
+        let overall = combine(&[ox_scaled, red_scaled], &order);
+        step(String::from("add the half-reactions and cancel common species"), &overall);
+        Ok(Redox {oxidation, reduction, overall, steps})
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// INTERNAL HELPERS
+///////////////////////////////////////////////////////////////////////////////
+
+/// H₂O, H⁺, OH⁻ or e⁻, which balancing adds as needed rather than taking
+/// them to be oxidized or reduced.
+fn is_medium_species(node: &Node) -> bool {
+    ["H2O", "H+", "OH-", "e-"]
+        .iter()
+        .any(|x| node.is_same_species(&Node::from_str(x).unwrap()))
+}
+
+/// Groups the species into (unbalanced) half-reactions, where species are in
+/// the same half if they share an element other than hydrogen or oxygen.
+///
+/// A lone group with a single species on one side (disproportionation or
+/// comproportionation) is split into one half per species on the other side.
+fn split(reaction: &Reaction) -> Result<Vec<Reaction>, RedoxError> {
+    let species = reaction.reactants.0
+        .iter()
+        .map(|x| (x.clone(), true))
+        .chain(reaction.products.0.iter().map(|x| (x.clone(), false)))
+        .filter(|(x, _)| !is_medium_species(x))
+        .collect::<Vec<_>>();
+    let key_elements = |node: &Node| -> BTreeSet<Element> {
+        let all = BTreeSet::<Element>::from_iter(node.atoms());
+        let keys = all
+            .iter()
+            .filter(|x| x.0 != "H" && x.0 != "O")
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        if keys.is_empty() {all} else {keys}
+    };
+    // Connected components, by repeatedly merging groups that share a key
+    // element.
+    let mut groups: Vec<(BTreeSet<Element>, Vec<usize>)> = Vec::new();
+    for (ix, (node, _)) in species.iter().enumerate() {
+        let mut keys = key_elements(node);
+        let mut members = vec![ix];
+        let mut rest = Vec::new();
+        for (group_keys, group_members) in groups {
+            if group_keys.is_disjoint(&keys) {
+                rest.push((group_keys, group_members));
+            } else {
+                keys.extend(group_keys);
+                members.extend(group_members);
+            }
+        }
+        members.sort_unstable();
+        rest.push((keys, members));
+        groups = rest;
+    }
+    let to_half = |members: &[usize]| -> Reaction {
+        let side = |is_reactant: bool| -> Sequence {
+            let xs = members
+                .iter()
+                .filter(|ix| species[**ix].1 == is_reactant)
+                .map(|ix| species[*ix].0.clone())
+                .collect();
+            Sequence(xs)
+        };
+        Reaction::new(side(true), side(false))
+    };
+    let halves = groups
+        .iter()
+        .map(|(_, members)| to_half(members))
+        .collect::<Vec<_>>();
+    match &halves[..] {
+        [x] if x.reactants.0.len() == 1 && x.products.0.len() > 1 => {
+            let halves = x.products.0
+                .iter()
+                .map(|product| {
+                    Reaction::new(x.reactants.clone(), Sequence(vec![product.clone()]))
+                })
+                .collect();
+            Ok(halves)
+        }
+        [x] if x.products.0.len() == 1 && x.reactants.0.len() > 1 => {
+            let halves = x.reactants.0
+                .iter()
+                .map(|reactant| {
+                    Reaction::new(Sequence(vec![reactant.clone()]), x.products.clone())
+                })
+                .collect();
+            Ok(halves)
+        }
+        _ if halves.len() < 2 => Err(RedoxError::CannotSplit),
+        _ => {
+            let one_sided = halves
+                .iter()
+                .any(|x| x.reactants.0.is_empty() || x.products.0.is_empty());
+            if one_sided {
+                Err(RedoxError::CannotSplit)
+            } else {
+                Ok(halves)
+            }
+        }
+    }
+}
+
+/// Balances a half-reaction by letting the linear balancer decide where (and
+/// whether) the species of the medium go.
+fn balance_half(half: &Reaction, medium: Medium) -> Result<Reaction, RedoxError> {
+    let mut candidate = half.clone();
+    candidate.reactants.0.extend(medium.species());
+    let balanced = match candidate.balance() {
+        Ok(x) => x,
+        // Species with a negative coefficient have been moved to the products.
+        Err(BalanceError::NonPositive {combination}) => combination,
+        Err(error) => {
+            return Err(RedoxError::Unbalanced {half: Box::new(half.clone()), error})
+        }
+    };
+    let contains = |xs: &Sequence, node: &Node| {
+        xs.0.iter().any(|x| x.is_same_species(node))
+    };
+    // The nullspace doesn't know which way round the reaction goes.
+    let balanced = if contains(&balanced.reactants, &half.reactants.0[0]) {
+        balanced
+    } else {
+        Reaction::new(balanced.products, balanced.reactants)
+    };
+    let keeps_sides = {
+        half.reactants.0.iter().all(|x| contains(&balanced.reactants, x)) &&
+        half.products.0.iter().all(|x| contains(&balanced.products, x))
+    };
+    if keeps_sides {
+        Ok(balanced)
+    } else {
+        let error = BalanceError::NonPositive {combination: balanced};
+        Err(RedoxError::Unbalanced {half: Box::new(half.clone()), error})
+    }
+}
+
+/// The number of electrons on the reactant and product side.
+fn electrons(reaction: &Reaction) -> (Number, Number) {
+    let count = |xs: &Sequence| -> Number {
+        xs.0.iter()
+            .filter(|x| x.is_electron())
+            .map(|x| x.root_coefficient())
+            .sum()
+    };
+    (count(&reaction.reactants), count(&reaction.products))
+}
+
+fn scale(reaction: &Reaction, factor: &Number) -> Reaction {
+    let scale = |xs: &Sequence| -> Sequence {
+        let xs = xs.0
+            .iter()
+            .map(|x| {
+                let coefficient = &x.root_coefficient() * factor;
+                x.clone().with_coefficient(coefficient)
+            })
+            .collect();
+        Sequence(xs)
+    };
+    Reaction::new(scale(&reaction.reactants), scale(&reaction.products))
+}
+
+/// Adds the reactions together, canceling species that appear on both sides
+/// and reducing the coefficients to the smallest integers.
+///
+/// Species are listed in the given order.
+fn combine(reactions: &[Reaction], order: &[Node]) -> Reaction {
+    // Net coefficient of each species; positive means reactant.
+    let mut net = Vec::<(Node, Number)>::new();
+    let mut add = |node: &Node, sign: Number| {
+        let coefficient = &node.root_coefficient() * &sign;
+        match net.iter_mut().find(|(x, _)| x.is_same_species(node)) {
+            Some((_, total)) => {
+                *total = &*total + &coefficient;
+            }
+            None => {
+                net.push((node.clone(), coefficient));
+            }
+        }
+    };
+    for reaction in reactions {
+        for x in reaction.reactants.0.iter() {
+            add(x, Number::int(1));
+        }
+        for x in reaction.products.0.iter() {
+            add(x, Number::int(-1));
+        }
+    }
+    let position = |node: &Node| {
+        order
+            .iter()
+            .position(|x| x.is_same_species(node))
+            .unwrap_or(order.len())
+    };
+    net.sort_by_key(|(x, _)| position(x));
+    let gcd = net
+        .iter()
+        .fold(Number::int(0), |acc, (_, x)| acc.gcd(x));
+    let mut left = Vec::<Node>::new();
+    let mut right = Vec::<Node>::new();
+    for (node, coefficient) in net {
+        if coefficient.is_zero() {
+            continue;
+        }
+        let coefficient = &coefficient / &gcd;
+        if coefficient > Number::int(0) {
+            left.push(node.with_coefficient(coefficient));
+        } else {
+            right.push(node.with_coefficient(-coefficient));
+        }
+    }
+    Reaction::new(Sequence(left), Sequence(right))
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Coefficient of the given species, negated for products.
+    fn coefficient(reaction: &Reaction, formula: &str) -> Number {
+        let target = Node::from_str(formula).unwrap();
+        let find = |xs: &Sequence| {
+            xs.0.iter()
+                .find(|x| x.is_same_species(&target))
+                .map(Node::root_coefficient)
+        };
+        find(&reaction.reactants)
+            .or_else(|| find(&reaction.products).map(|x| -x))
+            .unwrap_or(Number::int(0))
+    }
+
+    fn run(source: &str, medium: Medium, expected: &[(&str, i32)]) -> Redox {
+        let redox = Reaction::from_str(source)
+            .unwrap()
+            .balance_redox(medium)
+            .unwrap();
+        assert!(redox.oxidation.is_balanced());
+        assert!(redox.reduction.is_balanced());
+        assert!(redox.overall.is_balanced(), "{}", redox.overall.to_string());
+        for (formula, x) in expected {
+            assert_eq!(
+                coefficient(&redox.overall, formula),
+                Number::int(*x),
+                "{} in {}",
+                formula,
+                redox.overall.to_string(),
+            );
+        }
+        redox
+    }
+
+    #[test]
+    fn acidic() {
+        let redox = run("MnO4- + Fe^2+ -> Mn^2+ + Fe^3+", Medium::Acidic, &[
+            ("MnO4-", 1), ("Fe^2+", 5), ("H+", 8),
+            ("Mn^2+", -1), ("Fe^3+", -5), ("H2O", -4),
+        ]);
+        assert_eq!(electrons(&redox.reduction), (Number::int(5), Number::int(0)));
+        assert_eq!(redox.steps.len(), 7);
+        run("Cr2O7^2- + Fe^2+ + H+ -> Cr^3+ + Fe^3+ + H2O", Medium::Acidic, &[
+            ("Cr2O7^2-", 1), ("Fe^2+", 6), ("H+", 14),
+            ("Cr^3+", -2), ("Fe^3+", -6), ("H2O", -7),
+        ]);
+        run("Cu + NO3- -> Cu^2+ + NO", Medium::Acidic, &[
+            ("Cu", 3), ("NO3-", 2), ("H+", 8),
+            ("Cu^2+", -3), ("NO", -2), ("H2O", -4),
+        ]);
+    }
+
+    #[test]
+    fn basic() {
+        run("MnO4- + I- -> MnO2 + I2", Medium::Basic, &[
+            ("MnO4-", 2), ("I-", 6), ("H2O", 4),
+            ("MnO2", -2), ("I2", -3), ("OH-", -8),
+        ]);
+        // Disproportionation.
+        run("Cl2 -> Cl- + ClO3-", Medium::Basic, &[
+            ("Cl2", 3), ("OH-", 6),
+            ("Cl-", -5), ("ClO3-", -1), ("H2O", -3),
+        ]);
+    }
+
+    #[test]
+    fn errors() {
+        let run = |source: &str| {
+            Reaction::from_str(source)
+                .unwrap()
+                .balance_redox(Medium::Acidic)
+                .unwrap_err()
+        };
+        match run("AgNO3 + NaCl -> AgCl + NaNO3") {
+            RedoxError::CannotSplit => {}
+            x => panic!("{:?}", x),
+        }
+        // Both halves are oxidations.
+        match run("Fe^2+ + Cu^+ -> Fe^3+ + Cu^2+") {
+            RedoxError::NoElectronTransfer => {}
+            x => panic!("{:?}", x),
+        }
+        // Both Fe²⁺ and Sn²⁺ are oxidized.
+        match run("Fe^2+ + Sn^2+ + Cl2 -> Fe^3+ + Sn^4+ + Cl^-") {
+            RedoxError::TooManyHalves(3) => {}
+            x => panic!("{:?}", x),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Limiting reagents, theoretical yields and percent yield.
+use crate::numbers::Number;
+use crate::ast::expr::Expr;
+use crate::chem::data::*;
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+/// An amount of some species, as measured.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Quantity {
+    Grams(Number),
+    Moles(Number),
+    /// A volume of solution in liters, at a molarity in mol/L.
+    Solution {
+        liters: Number,
+        molarity: Number,
+    },
+}
+
+impl Quantity {
+    /// The amount in moles of the given species.
+    pub fn moles(&self, species: &Node) -> Result<Number, StoichiometryError> {
+        match self {
+            Quantity::Grams(x) => {
+                let mass = species
+                    .molar_mass()
+                    .ok_or_else(|| StoichiometryError::UnknownMolarMass(species.clone()))?;
+                Ok(x / &mass)
+            }
+            Quantity::Moles(x) => Ok(x.clone()),
+            Quantity::Solution {liters, molarity} => Ok(liters * molarity),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub description: String,
+    pub value: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct Yield {
+    /// The reaction the amounts refer to, balanced.
+    pub reaction: Reaction,
+    pub limiting: Node,
+    /// Moles of reaction the limiting reagent allows; i.e. its moles divided
+    /// by its coefficient.
+    pub extent: Number,
+    /// Theoretical yield of each product, in grams (or moles, without a
+    /// molar mass).
+    pub theoretical: Vec<(Node, Expr)>,
+    /// What's left of each excess reagent that was given, in the same units.
+    pub leftover: Vec<(Node, Expr)>,
+    /// Actual over theoretical yield, times 100.
+    pub percent_yield: Option<Number>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StoichiometryError {
+    Unbalanced(BalanceError),
+    /// A quantity for something that isn't a reactant, or an actual yield
+    /// for something that isn't a product.
+    UnknownSpecies(Node),
+    /// Given in grams, but the formula has unknown elements.
+    UnknownMolarMass(Node),
+    NoQuantities,
+}
+
+impl std::fmt::Display for StoichiometryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoichiometryError::Unbalanced(error) => {
+                write!(f, "cannot balance the reaction: {}", error)
+            }
+            StoichiometryError::UnknownSpecies(x) => {
+                write!(f, "{} is not in the reaction", x.formula())
+            }
+            StoichiometryError::UnknownMolarMass(x) => {
+                write!(f, "no molar mass for {}", x.formula())
+            }
+            StoichiometryError::NoQuantities => {
+                write!(f, "no reactant quantities given")
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// HELPERS
+///////////////////////////////////////////////////////////////////////////////
+
+fn moles(x: &Number) -> Expr {
+    Expr::Product(vec![Expr::Num(x.clone().into()), Expr::con("mol")])
+}
+
+/// In grams where the molar mass is known, otherwise in moles.
+fn amount(species: &Node, x: &Number) -> Expr {
+    match species.molar_mass() {
+        Some(mass) => Expr::Product(vec![Expr::Num((x * &mass).into()), Expr::con("g")]),
+        None => moles(x),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// LIMITING REAGENT
+///////////////////////////////////////////////////////////////////////////////
+
+impl Reaction {
+    /// Finds the limiting reagent among the given reactant quantities, and
+    /// from it the theoretical yield of each product and the leftover excess
+    /// reagents. Reactants without a quantity are taken to be in excess. The
+    /// percent yield is included if the actual yield of a product is given.
+    ///
+    /// The reaction is balanced first if needed.
+    pub fn limiting_reagent(
+        &self,
+        quantities: &[(Node, Quantity)],
+        actual: Option<(&Node, &Quantity)>,
+    ) -> Result<Yield, StoichiometryError> {
+        let reaction = if self.is_balanced() {
+            self.clone()
+        } else {
+            self.balance().map_err(StoichiometryError::Unbalanced)?
+        };
+        let mut steps = Vec::<Step>::new();
+        let mut step = |description: String, value: Expr| {
+            steps.push(Step {description, value});
+        };
+        // Each reactant given, its coefficient and its moles.
+        let mut given = Vec::<(Node, Number, Number)>::new();
+        for (species, quantity) in quantities.iter() {
+            let reactant = reaction.reactants.0
+                .iter()
+                .find(|x| x.is_same_species(species))
+                .ok_or_else(|| StoichiometryError::UnknownSpecies(species.clone()))?;
+            let n = quantity.moles(species)?;
+            step(format!("moles of {}", species.formula()), moles(&n));
+            given.push((reactant.clone(), reactant.root_coefficient(), n));
+        }
+        // The smallest moles of reaction wins.
+        let mut limiting = None::<(Node, Number)>;
+        for (reactant, coefficient, n) in given.iter() {
+            let extent = n / coefficient;
+            step(
+                format!("moles of reaction {} allows (÷ {})", reactant.formula(), coefficient),
+                moles(&extent),
+            );
+            if limiting.as_ref().is_none_or(|(_, x)| extent < *x) {
+                limiting = Some((reactant.clone(), extent));
+            }
+        }
+        let (limiting, extent) = limiting.ok_or(StoichiometryError::NoQuantities)?;
+        step(format!("{} is the limiting reagent", limiting.formula()), moles(&extent));
+        let mut theoretical = Vec::new();
+        let mut theoretical_moles = Vec::new();
+        for product in reaction.products.0.iter().filter(|x| !x.is_electron()) {
+            let n = &extent * &product.root_coefficient();
+            let value = amount(product, &n);
+            step(format!("theoretical yield of {}", product.formula()), value.clone());
+            theoretical.push((product.clone(), value));
+            theoretical_moles.push((product.clone(), n));
+        }
+        let mut leftover = Vec::new();
+        for (reactant, coefficient, n) in given.iter() {
+            if reactant.is_same_species(&limiting) {
+                continue
+            }
+            let left = n - &(&extent * coefficient);
+            let value = amount(reactant, &left);
+            step(format!("{} left over", reactant.formula()), value.clone());
+            leftover.push((reactant.clone(), value));
+        }
+        let percent_yield = match actual {
+            Some((species, quantity)) => {
+                let (product, expected) = theoretical_moles
+                    .iter()
+                    .find(|(x, _)| x.is_same_species(species))
+                    .ok_or_else(|| StoichiometryError::UnknownSpecies(species.clone()))?;
+                let n = quantity.moles(product)?;
+                let percent = &(&n / expected) * &Number::int(100);
+                step(String::from("percent yield"), Expr::Num(percent.clone().into()));
+                Some(percent)
+            }
+            None => None,
+        };
+        Ok(Yield {
+            reaction,
+            limiting,
+            extent,
+            theoretical,
+            leftover,
+            percent_yield,
+            steps,
+        })
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(source: &str) -> Node {
+        Node::from_str(source).unwrap()
+    }
+
+    #[test]
+    fn limiting_reagents() {
+        let reaction = Reaction::from_str("N2 + H2 -> NH3").unwrap();
+        let quantities = [
+            (node("N2"), Quantity::Moles(Number::int(2))),
+            (node("H2"), Quantity::Grams(Number::decimal("6.048").unwrap())),
+        ];
+        let actual = Quantity::Grams(Number::decimal("25.5465").unwrap());
+        let result = reaction
+            .limiting_reagent(&quantities, Some((&node("NH3"), &actual)))
+            .unwrap();
+        assert_eq!(result.limiting.formula(), "H2");
+        assert_eq!(result.extent, Number::int(1));
+        let render = |xs: &[(Node, Expr)]| {
+            xs.iter()
+                .map(|(x, value)| format!("{}: {}", x.formula(), value.clone().eval()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(render(&result.theoretical), vec!["NH3: 34.062 * g"]);
+        assert_eq!(render(&result.leftover), vec!["N2: 28.014 * g"]);
+        assert_eq!(result.percent_yield, Some(Number::int(75)));
+        assert_eq!(result.steps.len(), 8);
+        assert_eq!(result.steps[4].description, "H2 is the limiting reagent");
+        // A solution, with the other reactant in grams.
+        let reaction = Reaction::from_str("AgNO3 + NaCl -> AgCl + NaNO3").unwrap();
+        let quantities = [
+            (node("AgNO3"), Quantity::Solution {
+                liters: Number::decimal("0.1").unwrap(),
+                molarity: Number::decimal("0.5").unwrap(),
+            }),
+            (node("NaCl"), Quantity::Grams(Number::decimal("1.1688").unwrap())),
+        ];
+        let result = reaction.limiting_reagent(&quantities, None).unwrap();
+        assert_eq!(result.limiting.formula(), "NaCl");
+        assert_eq!(result.extent, Number::fraction(1, 50));
+        assert_eq!(render(&result.leftover), vec!["AgNO3: 5.09622 * g"]);
+        assert_eq!(result.percent_yield, None);
+        let missing = reaction.limiting_reagent(&[(node("KCl"), Quantity::Moles(Number::int(1)))], None);
+        assert!(matches!(missing, Err(StoichiometryError::UnknownSpecies(_))));
+        let empty = reaction.limiting_reagent(&[], None);
+        assert!(matches!(empty, Err(StoichiometryError::NoQuantities)));
+    }
+}
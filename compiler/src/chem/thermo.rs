@@ -0,0 +1,291 @@
+//! Standard thermodynamic data and reaction enthalpy, entropy and Gibbs
+//! energy.
+use crate::ast::expr::Expr;
+use crate::numbers::Number;
+use crate::chem::data::*;
+
+///////////////////////////////////////////////////////////////////////////////
+// DATA TYPES
+///////////////////////////////////////////////////////////////////////////////
+
+/// Standard values at 298.15 K and 1 bar, kept as written so they can be
+/// converted exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermoData {
+    pub formula: &'static str,
+    pub state: State,
+    /// Standard enthalpy of formation, ΔHf°, in kJ/mol.
+    pub enthalpy: &'static str,
+    /// Standard molar entropy, S°, in J/(mol·K).
+    pub entropy: &'static str,
+    /// Standard Gibbs energy of formation, ΔGf°, in kJ/mol.
+    pub gibbs: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub enum ThermoError {
+    /// Species that aren't in the table (in the given state).
+    Missing(Vec<Node>),
+}
+
+impl std::fmt::Display for ThermoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThermoError::Missing(xs) => {
+                let xs = xs
+                    .iter()
+                    .map(|x| {
+                        let state = match x.state() {
+                            Some(State::Aq) => "(aq)",
+                            Some(State::S) => "(s)",
+                            Some(State::L) => "(l)",
+                            Some(State::G) => "(g)",
+                            None => "",
+                        };
+                        format!("{}{}", x.formula(), state)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "no thermodynamic data for {}", xs)
+            }
+        }
+    }
+}
+
+impl ThermoData {
+    /// The entry for the species in the given state. Species without a state
+    /// are taken to be in their standard state, which is listed first.
+    ///
+    /// Species are matched by composition and charge, so `C2H6O` finds
+    /// ethanol.
+    pub fn lookup(node: &Node) -> Option<&'static ThermoData> {
+        THERMO_TABLE.iter().find(|x| {
+            let species = Node::from_str(x.formula).unwrap();
+            let same_state = match node.state() {
+                Some(state) => *state == x.state,
+                None => true,
+            };
+            same_state && node.is_same_species(&species)
+        })
+    }
+    pub fn all() -> &'static [ThermoData] {
+        &THERMO_TABLE
+    }
+    pub fn enthalpy(&self) -> Number {
+        Number::decimal(self.enthalpy).unwrap()
+    }
+    pub fn entropy(&self) -> Number {
+        Number::decimal(self.entropy).unwrap()
+    }
+    pub fn gibbs(&self) -> Number {
+        Number::decimal(self.gibbs).unwrap()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// REACTION QUANTITIES
+///////////////////////////////////////////////////////////////////////////////
+
+pub(crate) fn kilojoules_per_mole(x: Number) -> Expr {
+    Expr::ratio(
+        Expr::Product(vec![Expr::Num(x.into()), Expr::con("kJ")]),
+        Expr::con("mol"),
+    )
+}
+
+fn joules_per_mole_kelvin(x: Number) -> Expr {
+    Expr::ratio(
+        Expr::Product(vec![Expr::Num(x.into()), Expr::con("J")]),
+        Expr::Product(vec![Expr::con("mol"), Expr::con("K")]),
+    )
+}
+
+impl Reaction {
+    /// Products minus reactants, weighted by coefficients. Every species
+    /// without an entry is reported at once.
+    fn standard_change(&self, value: fn(&ThermoData) -> Number) -> Result<Number, ThermoError> {
+        let mut missing = Vec::new();
+        let mut total = Number::int(0);
+        let sides = [(&self.reactants, Number::int(-1)), (&self.products, Number::int(1))];
+        for (xs, sign) in sides.iter() {
+            for x in xs.0.iter() {
+                match ThermoData::lookup(x) {
+                    Some(data) => {
+                        total = &total + &(&(sign * &x.root_coefficient()) * &value(data));
+                    }
+                    None => missing.push(x.clone()),
+                }
+            }
+        }
+        if missing.is_empty() {
+            Ok(total)
+        } else {
+            Err(ThermoError::Missing(missing))
+        }
+    }
+    /// ΔH°rxn in kJ/mol, from enthalpies of formation.
+    pub fn enthalpy_change(&self) -> Result<Number, ThermoError> {
+        self.standard_change(ThermoData::enthalpy)
+    }
+    /// ΔS°rxn in J/(mol·K).
+    pub fn entropy_change(&self) -> Result<Number, ThermoError> {
+        self.standard_change(ThermoData::entropy)
+    }
+    /// ΔG°rxn in kJ/mol at the given temperature (in kelvin), as
+    /// `ΔH° − TΔS°`; i.e. assuming ΔH° and ΔS° don't depend on temperature.
+    pub fn gibbs_change(&self, temperature: &Number) -> Result<Number, ThermoError> {
+        let enthalpy = self.enthalpy_change()?;
+        let entropy = self.entropy_change()?;
+        Ok(&enthalpy - &(&(temperature * &entropy) / &Number::int(1000)))
+    }
+    /// ΔH°rxn with units, e.g. `−890.36 * kJ * mol⁻¹`.
+    pub fn enthalpy(&self) -> Result<Expr, ThermoError> {
+        self.enthalpy_change().map(kilojoules_per_mole)
+    }
+    /// ΔS°rxn with units, e.g. `−243 * J * mol⁻¹ * K⁻¹`.
+    pub fn entropy(&self) -> Result<Expr, ThermoError> {
+        self.entropy_change().map(joules_per_mole_kelvin)
+    }
+    /// ΔG°rxn at the given temperature (in kelvin), with units.
+    pub fn gibbs(&self, temperature: &Number) -> Result<Expr, ThermoError> {
+        self.gibbs_change(temperature).map(kilojoules_per_mole)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// TABLE
+///////////////////////////////////////////////////////////////////////////////
+
+const fn entry(
+    formula: &'static str,
+    state: State,
+    enthalpy: &'static str,
+    entropy: &'static str,
+    gibbs: &'static str,
+) -> ThermoData {
+    ThermoData {formula, state, enthalpy, entropy, gibbs}
+}
+
+/// Grouped by element, with the standard state of each species first.
+static THERMO_TABLE: [ThermoData; 60] = [
+    // Elements
+    entry("H2", State::G, "0", "130.68", "0"),
+    entry("O2", State::G, "0", "205.15", "0"),
+    entry("O3", State::G, "142.7", "238.93", "163.2"),
+    entry("N2", State::G, "0", "191.61", "0"),
+    entry("C", State::S, "0", "5.74", "0"),
+    entry("Cl2", State::G, "0", "223.07", "0"),
+    entry("Br2", State::L, "0", "152.23", "0"),
+    entry("I2", State::S, "0", "116.14", "0"),
+    entry("S", State::S, "0", "31.80", "0"),
+    entry("Na", State::S, "0", "51.21", "0"),
+    entry("K", State::S, "0", "64.18", "0"),
+    entry("Mg", State::S, "0", "32.68", "0"),
+    entry("Ca", State::S, "0", "41.42", "0"),
+    entry("Al", State::S, "0", "28.33", "0"),
+    entry("Fe", State::S, "0", "27.28", "0"),
+    entry("Cu", State::S, "0", "33.15", "0"),
+    entry("Zn", State::S, "0", "41.63", "0"),
+    entry("Ag", State::S, "0", "42.55", "0"),
+    // Hydrogen and oxygen
+    entry("H2O", State::L, "-285.83", "69.91", "-237.13"),
+    entry("H2O", State::G, "-241.82", "188.83", "-228.57"),
+    entry("H2O2", State::L, "-187.78", "109.6", "-120.35"),
+    // Carbon
+    entry("CO", State::G, "-110.53", "197.67", "-137.17"),
+    entry("CO2", State::G, "-393.51", "213.74", "-394.36"),
+    entry("CH4", State::G, "-74.81", "186.26", "-50.72"),
+    entry("C2H2", State::G, "226.73", "200.94", "209.20"),
+    entry("C2H4", State::G, "52.26", "219.56", "68.15"),
+    entry("C2H6", State::G, "-84.68", "229.60", "-32.82"),
+    entry("C3H8", State::G, "-103.85", "270.02", "-23.49"),
+    entry("C8H18", State::L, "-249.9", "361.1", "6.4"),
+    entry("CH3OH", State::L, "-238.66", "126.8", "-166.27"),
+    entry("C2H5OH", State::L, "-277.69", "160.7", "-174.78"),
+    entry("C6H12O6", State::S, "-1273.3", "212.1", "-910.4"),
+    // Nitrogen
+    entry("NH3", State::G, "-46.11", "192.45", "-16.45"),
+    entry("NO", State::G, "90.25", "210.76", "86.55"),
+    entry("NO2", State::G, "33.18", "240.06", "51.31"),
+    entry("N2O4", State::G, "9.16", "304.29", "97.89"),
+    // Sulfur
+    entry("SO2", State::G, "-296.83", "248.22", "-300.19"),
+    entry("SO3", State::G, "-395.72", "256.76", "-371.06"),
+    entry("H2S", State::G, "-20.63", "205.79", "-33.56"),
+    // Halogens
+    entry("HCl", State::G, "-92.31", "186.91", "-95.30"),
+    entry("HBr", State::G, "-36.40", "198.70", "-53.45"),
+    entry("HI", State::G, "26.48", "206.59", "1.70"),
+    // Metal compounds
+    entry("NaCl", State::S, "-411.15", "72.13", "-384.14"),
+    entry("NaOH", State::S, "-425.61", "64.46", "-379.49"),
+    entry("KCl", State::S, "-436.75", "82.59", "-409.14"),
+    entry("MgO", State::S, "-601.70", "26.94", "-569.43"),
+    entry("CaO", State::S, "-635.09", "39.75", "-604.03"),
+    entry("CaCO3", State::S, "-1206.9", "92.9", "-1128.8"),
+    entry("Ca(OH)2", State::S, "-986.09", "83.39", "-898.49"),
+    entry("Al2O3", State::S, "-1675.7", "50.92", "-1582.3"),
+    entry("Fe2O3", State::S, "-824.2", "87.40", "-742.2"),
+    entry("CuO", State::S, "-157.3", "42.63", "-129.7"),
+    entry("ZnO", State::S, "-348.28", "43.64", "-318.30"),
+    entry("AgCl", State::S, "-127.07", "96.2", "-109.79"),
+    // Aqueous ions; H⁺ is zero by convention.
+    entry("H^+", State::Aq, "0", "0", "0"),
+    entry("OH^-", State::Aq, "-229.99", "-10.75", "-157.24"),
+    entry("Na^+", State::Aq, "-240.12", "59.0", "-261.91"),
+    entry("Cl^-", State::Aq, "-167.16", "56.5", "-131.23"),
+    entry("Ag^+", State::Aq, "105.58", "72.68", "77.11"),
+    entry("Cu^2+", State::Aq, "64.77", "-99.6", "65.49"),
+];
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table() {
+        for data in ThermoData::all() {
+            let species = Node::from_str_strict(data.formula).unwrap();
+            assert_eq!(ThermoData::lookup(&species).map(|x| x.formula), Some(data.formula));
+            data.enthalpy();
+            data.entropy();
+            data.gibbs();
+        }
+        let steam = Node::from_str("H2O(g)").unwrap();
+        assert_eq!(ThermoData::lookup(&steam).unwrap().enthalpy(), Number::fraction(-24182, 100));
+        let ethanol = Node::from_str("C2H6O(l)").unwrap();
+        assert_eq!(ThermoData::lookup(&ethanol).unwrap().formula, "C2H5OH");
+    }
+
+    #[test]
+    fn reaction_quantities() {
+        let methane = Reaction::from_str("CH4(g) + 2O2(g) -> CO2(g) + 2H2O(l)").unwrap();
+        assert_eq!(methane.enthalpy_change().unwrap(), Number::fraction(-89036, 100));
+        assert_eq!(methane.entropy_change().unwrap(), Number::fraction(-24300, 100));
+        let gibbs = methane.gibbs_change(&Number::fraction(29815, 100)).unwrap();
+        assert_eq!(gibbs, Number::decimal("-817.90955").unwrap());
+        assert_eq!(methane.enthalpy().unwrap().eval().to_string(), "-890.36 * kJ * mol⁻¹");
+        // Close to the tabulated ΔGf° values.
+        let tabulated = methane.standard_change(ThermoData::gibbs).unwrap();
+        assert!((&gibbs - &tabulated).abs() < Number::int(1));
+        // The arrow, not the order written, decides what's formed.
+        let forward = Reaction::from_str("2H2(g) + O2(g) -> 2H2O(l)").unwrap();
+        let backward = Reaction::from_str("2H2O(l) <- 2H2(g) + O2(g)").unwrap();
+        assert_eq!(backward.enthalpy_change().unwrap(), forward.enthalpy_change().unwrap());
+        assert_eq!(forward.enthalpy_change().unwrap(), Number::fraction(-57166, 100));
+        let unknown = Reaction::from_str("H2O2(aq) + XeF2 -> H2O(l) + O2").unwrap();
+        match unknown.enthalpy_change() {
+            Err(ThermoError::Missing(xs)) => {
+                let xs = xs.iter().map(Node::formula).collect::<Vec<_>>();
+                assert_eq!(xs, vec!["H2O2", "XeF2"]);
+            }
+            x => panic!("{:?}", x),
+        }
+    }
+}
@@ -83,7 +83,7 @@ fn order_solver(xs: Vec<(HashSet<usize>, char)>) -> Vec<char> {
         );
         done = layout.iter().all(|x| x.is_some());
         let flip = !flip;
-        cycle_counter = cycle_counter + 1;
+        cycle_counter += 1;
         assert!(cycle_counter <= 100);
     }
     layout
@@ -557,76 +557,131 @@ impl Matrix {
         assert!(new_matrix.validate());
         new_matrix
     }
+    /// Reduces the matrix to row echelon form (in place), swapping rows
+    /// whenever the current pivot is zero.
+    ///
+    /// Columns without any usable pivot are skipped, so singular matrices are
+    /// fine.
     pub fn forward_elimination(&mut self) {
-        let row_len = self.row_len();
-        let col_len = self.col_len();
-        for i in 0..(col_len) {
-            for j in (i + 1)..row_len {
-                let new_row = (|| -> Option<Row> {
-                    let i_mult = {
-                        let l = self.unsafe_get((j, i));
-                        let r = self.unsafe_get((i, i));
-                        l.save_div(r).unwrap_or_else(|| panic!(
-                            "cannot div by zero!!!"
-                        ))
-                    };
-                    let i_mult = -i_mult;
-                    let j_row = self
-                        .unsafe_get_row(j);
-                    let i_row = self
-                        .unsafe_get_row(i)
-                        .clone()
-                        .mul_each(&i_mult)
-                        .add(j_row);
-                    Some(i_row)
-                })();
-                let new_row = new_row.unwrap();
+        let (row_len, col_len) = self.len();
+        let mut pivot_row = 0;
+        for col in 0..col_len {
+            if pivot_row == row_len {
+                break;
+            }
+            let found = (pivot_row..row_len).find(|r| !self.0[*r].0[col].is_zero());
+            let found = match found {
+                Some(r) => r,
+                None => continue,
+            };
+            self.0.swap(pivot_row, found);
+            for j in (pivot_row + 1)..row_len {
+                let i_mult = {
+                    let l = self.unsafe_get((j, col));
+                    let r = self.unsafe_get((pivot_row, col));
+                    -(l / r)
+                };
+                let new_row = self
+                    .unsafe_get_row(pivot_row)
+                    .clone()
+                    .mul_each(&i_mult)
+                    .add(self.unsafe_get_row(j));
                 self.replace_row(j, new_row);
             }
+            pivot_row += 1;
         }
     }
-    pub fn solve(&self) -> Column {
-        let mut this = self.clone();
-        let row_len = this.row_len();
-        let col_len = this.col_len();
-        this.forward_elimination();
-        // Solution Vector
-        let mut solution = (0..(col_len-1))
-            .into_iter()
-            .map(|_| Number::int(0))
+    /// Solves the augmented matrix `[A | b]`, where the last column is `b`.
+    ///
+    /// Returns `None` if the system is inconsistent or doesn't have a unique
+    /// solution.
+    pub fn solve(&self) -> Option<Column> {
+        let col_len = self.col_len();
+        let (reduced, pivots) = self.reduce();
+        // A pivot in the augmented column means `0 = 1`.
+        let unknowns = col_len - 1;
+        if pivots.contains(&unknowns) || pivots.len() != unknowns {
+            return None
+        }
+        let solution = (0..unknowns)
+            .map(|row_ix| reduced.unsafe_get((row_ix, unknowns)).clone())
             .collect::<Vec<_>>();
-        let mut solution = Matrix::column_vector(solution).unwrap();
-        for i in (0..(row_len)).rev() {
-            let l = this.get((i, col_len - 1)).unwrap().clone();
-            let r = this.new_from(
-                i..=i,
-                0..(col_len - 1),
-            );
-            let r = r
-                .dot(&solution)
-                .unwrap()
-                .unpack_singleton()
-                .unwrap();
-            let current = {
-                let num = (l - r);
-                let den = this.get((i, i)).unwrap();
-                num.save_div(den).unwrap_or_else(|| panic!(
-                    "cannot div by zero!!!"
-                ))
+        Some(Column(solution))
+    }
+    /// Gauss-Jordan elimination with row swapping; returns the reduced
+    /// matrix along with the pivot column of each non-zero row.
+    fn reduce(&self) -> (Matrix, Vec<Index>) {
+        let (row_len, col_len) = self.len();
+        let mut rows = self.0.clone();
+        let mut pivots = Vec::<Index>::new();
+        for col in 0..col_len {
+            let pivot_row = pivots.len();
+            if pivot_row == row_len {
+                break;
+            }
+            let found = (pivot_row..row_len).find(|r| !rows[*r].0[col].is_zero());
+            let found = match found {
+                Some(r) => r,
+                None => continue,
             };
-            solution.set((i, 0), current);
+            rows.swap(pivot_row, found);
+            let pivot = rows[pivot_row].0[col].clone();
+            rows[pivot_row] = rows[pivot_row].clone().div_each(&pivot);
+            for r in 0..row_len {
+                if r != pivot_row && !rows[r].0[col].is_zero() {
+                    let mult = -rows[r].0[col].clone();
+                    rows[r] = rows[pivot_row]
+                        .clone()
+                        .mul_each(&mult)
+                        .add(&rows[r]);
+                }
+            }
+            pivots.push(col);
         }
-        solution.unpack_column_vector().unwrap()
+        (Matrix(rows), pivots)
+    }
+    /// Reduced row echelon form.
+    pub fn rref(&self) -> Matrix {
+        self.reduce().0
+    }
+    /// The column index of each pivot in the reduced row echelon form.
+    pub fn pivot_columns(&self) -> Vec<Index> {
+        self.reduce().1
+    }
+    pub fn rank(&self) -> usize {
+        self.pivot_columns().len()
     }
-    pub fn nullspace(&self) -> Column {
+    /// A basis for the set of vectors `x` satisfying `Ax = 0`.
+    ///
+    /// There is one basis vector per free (non-pivot) column, where that free
+    /// variable is set to one and the others to zero. An empty result means
+    /// only the zero vector is a solution.
+    pub fn nullspace_basis(&self) -> Vec<Column> {
         let col_len = self.col_len();
-        let mut solution = self.solve();
-        for ix in 0..=col_len {
-            if ix > solution.0.len() {
-                solution.0.push(Number::int(1));
-            }
+        let (reduced, pivots) = self.reduce();
+        (0..col_len)
+            .filter(|c| !pivots.contains(c))
+            .map(|free| {
+                let mut solution = Column::zeros(col_len);
+                solution.0[free] = Number::int(1);
+                for (row_ix, pivot) in pivots.iter().enumerate() {
+                    solution.0[*pivot] = -reduced.unsafe_get((row_ix, free));
+                }
+                solution
+            })
+            .collect()
+    }
+    /// The vector `x` satisfying `Ax = 0`, provided the solution set is a
+    /// single line (i.e. exactly one free variable).
+    ///
+    /// The free variable is set to one.
+    pub fn nullspace(&self) -> Option<Column> {
+        let mut basis = self.nullspace_basis();
+        if basis.len() == 1 {
+            basis.pop()
+        } else {
+            None
         }
-        solution
     }
 }
 
@@ -731,9 +786,79 @@ pub fn main() {
     let solution = matrix
         .clone()
         // .non_zero_diagnal()
-        .nullspace();
+        .nullspace()
+        .unwrap();
     println!("-----------");
     // println!("-----------");
     // println!("=");
     println!("{}", solution)
-}
\ No newline at end of file
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(xs: &[i32]) -> Vec<Number> {
+        xs.iter().map(|x| Number::int(*x)).collect()
+    }
+
+    #[test]
+    fn rref_with_zero_pivot() {
+        // The leading zero used to panic with "cannot div by zero!!!".
+        let matrix: Matrix = matrix!{
+            Number::int(0), Number::int(2), Number::int(4);
+            Number::int(1), Number::int(1), Number::int(1);
+            Number::int(2), Number::int(2), Number::int(2);
+        };
+        let expected = Matrix::from_rows(vec![
+            ints(&[1, 0, -1]),
+            ints(&[0, 1, 2]),
+            ints(&[0, 0, 0]),
+        ]).unwrap();
+        assert_eq!(matrix.rref(), expected);
+        assert_eq!(matrix.rank(), 2);
+        let mut echelon = matrix.clone();
+        echelon.forward_elimination();
+        assert_eq!(echelon.unsafe_get_row(2), &Row(ints(&[0, 0, 0])));
+    }
+
+    #[test]
+    fn nullspace_basis() {
+        let matrix: Matrix = matrix!{
+            Number::int(1), Number::int(2), Number::int(3), Number::int(4);
+            Number::int(2), Number::int(4), Number::int(6), Number::int(8);
+        };
+        let basis = matrix.nullspace_basis();
+        assert_eq!(basis.len(), 3);
+        assert_eq!(matrix.nullspace(), None);
+        for x in basis {
+            let x = Matrix::column_vector(x).unwrap();
+            let product = matrix.dot(&x).unwrap();
+            assert!(product.unpack_column_vector().unwrap().0.iter().all(Number::is_zero));
+        }
+        let identity: Matrix = matrix!{
+            Number::int(1), Number::int(0);
+            Number::int(0), Number::int(1);
+        };
+        assert!(identity.nullspace_basis().is_empty());
+    }
+
+    #[test]
+    fn solve_augmented() {
+        let matrix: Matrix = matrix!{
+            Number::int(0), Number::int(1), Number::int(3);
+            Number::int(2), Number::int(0), Number::int(4);
+        };
+        assert_eq!(matrix.solve(), Some(Column(ints(&[2, 3]))));
+        let inconsistent: Matrix = matrix!{
+            Number::int(1), Number::int(1), Number::int(1);
+            Number::int(1), Number::int(1), Number::int(2);
+        };
+        assert_eq!(inconsistent.solve(), None);
+    }
+}
@@ -1,6 +1,10 @@
-use num::{FromPrimitive, ToPrimitive, BigRational, BigInt, Signed};
+use std::convert::TryFrom;
+use num::{FromPrimitive, ToPrimitive, BigRational, BigInt, Signed, Zero};
 
 
+/// The largest exponent (in magnitude) that `Number::decimal` accepts.
+pub const MAX_DECIMAL_EXPONENT: u32 = 10_000;
+
 /// Rational number type for exact answers with no roundoff error.
 #[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct Number(BigRational);
@@ -23,6 +27,39 @@ impl Number {
     pub fn double(x: impl Into<f64>) -> Self {
         Number(BigRational::from_f64(x.into()).unwrap())
     }
+    /// Constructs a `Number` from a decimal string such as `-12.5` or
+    /// `1.8e-5`, exactly (i.e. without going through `f64`).
+    ///
+    /// `None` if the exponent is more than `MAX_DECIMAL_EXPONENT` in
+    /// magnitude, since the exact value would take too long to compute.
+    pub fn decimal(source: &str) -> Option<Self> {
+        let source = source.trim();
+        let (mantissa, exponent) = match source.find(['e', 'E']) {
+            Some(ix) => (&source[..ix], source[ix + 1..].parse::<i32>().ok()?),
+            None => (source, 0),
+        };
+        if exponent.unsigned_abs() > MAX_DECIMAL_EXPONENT {
+            return None
+        }
+        let (sign, mantissa) = match mantissa.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+        };
+        let (whole, fraction) = match mantissa.find('.') {
+            Some(ix) => (&mantissa[..ix], &mantissa[ix + 1..]),
+            None => (mantissa, ""),
+        };
+        let is_digits = |x: &str| x.chars().all(|c| c.is_ascii_digit());
+        if whole.len() + fraction.len() == 0 || !is_digits(whole) || !is_digits(fraction) {
+            return None
+        }
+        let digits = BigInt::parse_bytes(format!("{}{}", whole, fraction).as_bytes(), 10)?;
+        let ten = BigRational::from_i64(10).unwrap();
+        let places = i32::try_from(fraction.len()).ok()?;
+        let scale = ten.pow(exponent.checked_sub(places)?);
+        let value = BigRational::from_integer(digits) * scale;
+        Some(Number(value * BigRational::from_i64(sign).unwrap()))
+    }
     /// Constructs a `Number` form an usize value.
     pub fn from_usize(x: usize) -> Self {
         Number(BigRational::from_i128(x as i128).unwrap())
@@ -68,6 +105,130 @@ impl Number {
             Some(self / den)
         }
     }
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+    pub fn is_integer(&self) -> bool {
+        self.0.is_integer()
+    }
+    /// The nearest integer, rounding halves away from zero.
+    pub fn round(&self) -> Number {
+        Number(self.0.round())
+    }
+    /// Rounded to the given number of decimal places.
+    pub fn round_to(&self, places: u32) -> Number {
+        let scale = BigRational::from_integer(num::pow(BigInt::from(10), places as usize));
+        Number((&self.0 * &scale).round() / scale)
+    }
+    pub fn to_f64(&self) -> Option<f64> {
+        self.0.to_f64()
+    }
+    /// The common (base ten) logarithm, or `None` unless positive.
+    ///
+    /// Exact for powers of ten (e.g. `log10(0.001) = -3`); otherwise computed
+    /// with `f64` and rounded to 12 decimal places, which is about as much as
+    /// an `f64` logarithm gets right.
+    pub fn log10(&self) -> Option<Number> {
+        use num::{Integer, One};
+        if !self.0.is_positive() {
+            return None
+        }
+        let power_of_ten = |x: &BigInt| -> Option<i128> {
+            let ten = BigInt::from(10);
+            let mut x = x.clone();
+            let mut n = 0;
+            while x.is_multiple_of(&ten) {
+                x /= 10;
+                n += 1;
+            }
+            if x.is_one() {Some(n)} else {None}
+        };
+        if self.0.denom().is_one() {
+            if let Some(n) = power_of_ten(self.0.numer()) {
+                return Some(Number::int(n))
+            }
+        }
+        if self.0.numer().is_one() {
+            if let Some(n) = power_of_ten(self.0.denom()) {
+                return Some(Number::int(-n))
+            }
+        }
+        let x = self.to_f64()?.log10();
+        Some(Number(BigRational::from_f64(x)?).round_to(12))
+    }
+    /// Ten to the power of this number; exact for integers up to a thousand
+    /// in magnitude, otherwise computed with `f64`. `None` if the result
+    /// overflows (or underflows to zero in) an `f64`.
+    pub fn exp10(&self) -> Option<Number> {
+        match self.unpack_integer() {
+            Some(n) if n.unsigned_abs() <= 1000 => {
+                let ten = BigRational::from_integer(BigInt::from(10));
+                Some(Number(num::pow::Pow::pow(ten, n as i32)))
+            }
+            _ => {
+                let x = 10f64.powf(self.to_f64()?);
+                if x == 0.0 {
+                    return None
+                }
+                Some(Number(BigRational::from_f64(x)?))
+            }
+        }
+    }
+    /// The square root, or `None` if negative. Exact for squares of
+    /// rationals, otherwise computed with `f64`.
+    pub fn sqrt(&self) -> Option<Number> {
+        use num::integer::Roots;
+        if self.0.is_negative() {
+            return None
+        }
+        let numer = self.0.numer().sqrt();
+        let denom = self.0.denom().sqrt();
+        if &(&numer * &numer) == self.0.numer() && &(&denom * &denom) == self.0.denom() {
+            return Some(Number(BigRational::new(numer, denom)))
+        }
+        let x = self.to_f64()?.sqrt();
+        Some(Number(BigRational::from_f64(x)?))
+    }
+    /// The largest rational that divides both values into integers, i.e.
+    /// `gcd(a/b, c/d) = gcd(a, c) / lcm(b, d)`.
+    ///
+    /// Dividing a list of numbers by their common `gcd` yields the smallest
+    /// integers with the same ratios.
+    pub fn gcd(&self, other: &Number) -> Number {
+        use num::Integer;
+        let numer = self.0.numer().gcd(other.0.numer());
+        let denom = self.0.denom().lcm(other.0.denom());
+        Number(BigRational::new(numer, denom))
+    }
+    /// The exact decimal representation (e.g. `0.95`), if there is one; that
+    /// is, if the denominator has no prime factors other than 2 and 5.
+    pub fn to_exact_decimal(&self) -> Option<String> {
+        use num::Integer;
+        let mut den = self.0.denom().clone();
+        let mut places = (0, 0);
+        while den.is_multiple_of(&BigInt::from(2)) {
+            den /= 2;
+            places.0 += 1;
+        }
+        while den.is_multiple_of(&BigInt::from(5)) {
+            den /= 5;
+            places.1 += 1;
+        }
+        if den != BigInt::from(1) {
+            return None
+        }
+        let places = std::cmp::max(places.0, places.1);
+        let scale = BigRational::from_integer(num::pow(BigInt::from(10), places));
+        let scaled = (&self.0 * scale).to_integer();
+        let digits = format!("{:0>width$}", scaled.abs(), width = places + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - places);
+        let sign = if scaled.is_negative() {"-"} else {""};
+        if fraction.is_empty() {
+            Some(format!("{}{}", sign, whole))
+        } else {
+            Some(format!("{}{}.{}", sign, whole, fraction))
+        }
+    }
 }
 
 impl std::ops::Add for Number {
@@ -132,6 +293,19 @@ impl std::ops::Mul for &mut Number {
     fn mul(self, other: Self) -> Number {Number(self.0.clone() * other.0.clone())}
 }
 
+impl std::iter::Sum for Number {
+    fn sum<I: Iterator<Item = Number>>(iter: I) -> Self {
+        iter.fold(Number::int(0), |l, r| l + r)
+    }
+}
+
+impl From<BigRational> for Number {
+    fn from(x: BigRational) -> Self {Number(x)}
+}
+impl From<Number> for BigRational {
+    fn from(x: Number) -> Self {x.0}
+}
+
 impl std::fmt::Debug for Number {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let x = &self.0;
@@ -188,3 +362,28 @@ impl std::fmt::Display for Number {
     }
 }
 
+
+
+///////////////////////////////////////////////////////////////////////////////
+// TESTS
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimals() {
+        assert_eq!(Number::decimal("-12.5"), Some(Number::fraction(-25, 2)));
+        assert_eq!(Number::decimal("1.8e-5"), Some(Number::fraction(18, 1_000_000)));
+        assert_eq!(Number::decimal("2E3"), Some(Number::int(2000)));
+        assert_eq!(Number::decimal("1e10000").map(|x| x.log10()), Some(Some(Number::int(10000))));
+        assert_eq!(Number::decimal("1e10001"), None);
+        assert_eq!(Number::decimal("1e99999999"), None);
+        assert_eq!(Number::decimal("1.5e-2147483648"), None);
+        assert_eq!(Number::decimal("1.5e2147483647"), None);
+        assert_eq!(Number::decimal("1.5e"), None);
+        assert_eq!(Number::decimal("."), None);
+    }
+}
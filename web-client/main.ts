@@ -149,6 +149,10 @@ function add_form_entry_reaction(
                     rust.then(module => {
                         const input = get_input_value(element);
                         const result = module.balance_reaction(input) as any;
+                        if (result['error']) {
+                            add_error(result['error']);
+                            return;
+                        }
                         const input_ast = result['input_ast'];
                         const input_str = result['input_str'];
                         const output_ast = result['output_ast'];
@@ -64,7 +64,7 @@ pub fn chem_eval(source: &str) -> js_sys::Object {
 pub fn balance_reaction(source: &str) -> js_sys::Object {
     use compiler::chem::data::Reaction;
     // Roughly equivalent to `let obj = new Object; obj.foo = "bar";`
-    let mut reaction = Reaction::from_str(source).unwrap();
+    let reaction = Reaction::from_str(source).unwrap();
     let object = js_sys::Object::new();
     js_sys::Reflect::set(
         &object,
@@ -76,12 +76,64 @@ pub fn balance_reaction(source: &str) -> js_sys::Object {
         &"input_str".into(),
         &reaction.to_string().into()
     );
-    reaction.balance();
+    let balanced = match reaction.balance() {
+        Ok(x) => x,
+        Err(error) => {
+            js_sys::Reflect::set(
+                &object,
+                &"error".into(),
+                &error.to_string().into()
+            );
+            return object
+        }
+    };
     js_sys::Reflect::set(
         &object,
         &"output_ast".into(),
-        &format!("{:#?}", reaction).into()
+        &format!("{:#?}", balanced).into()
+    );
+    js_sys::Reflect::set(
+        &object,
+        &"output_str".into(),
+        &balanced.to_string().into()
     );
+    object
+}
+
+/// Classifies the reactants and, where they follow, fills in the products.
+#[wasm_bindgen]
+pub fn predict_reaction(source: &str) -> js_sys::Object {
+    use compiler::chem::data::Sequence;
+    let object = js_sys::Object::new();
+    let reactants = match Sequence::from_str(source) {
+        Some(x) => x,
+        None => {
+            js_sys::Reflect::set(
+                &object,
+                &"error".into(),
+                &"cannot parse the reactants".into()
+            );
+            return object
+        }
+    };
+    if let Some(kind) = reactants.reaction_type() {
+        js_sys::Reflect::set(
+            &object,
+            &"type".into(),
+            &kind.to_string().into()
+        );
+    }
+    let reaction = match reactants.predict_products() {
+        Ok(x) => x,
+        Err(error) => {
+            js_sys::Reflect::set(
+                &object,
+                &"error".into(),
+                &error.to_string().into()
+            );
+            return object
+        }
+    };
     js_sys::Reflect::set(
         &object,
         &"output_str".into(),